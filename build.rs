@@ -0,0 +1,4 @@
+// Пересобираем бинарник при добавлении миграций: sqlx::migrate! встраивает их при компиляции
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
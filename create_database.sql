@@ -1,11 +1,8 @@
--- Скрипт для создания базы данных и применения миграций
+-- Скрипт для создания базы данных
 -- Выполните этот скрипт в pgAdmin или через psql
 
 -- Создаем базу данных (если её еще нет)
 CREATE DATABASE alien_game;
 
--- Подключаемся к базе данных (в psql: \c alien_game)
-
--- Применяем миграции
-\i migrations/20240101000001_create_tables.sql
-
+-- Миграции применяет сервер при старте (RUN_MIGRATIONS=true)
+-- или команда: cargo run -- migrate up
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS claims;
+DROP TABLE IF EXISTS scores;
+DROP TABLE IF EXISTS users;
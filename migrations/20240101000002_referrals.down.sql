@@ -0,0 +1,9 @@
+DROP TABLE IF EXISTS referral_rewards;
+DROP TABLE IF EXISTS balance_transactions;
+DROP TABLE IF EXISTS balances;
+
+DROP INDEX IF EXISTS idx_users_referred_by;
+DROP INDEX IF EXISTS idx_users_referral_code;
+ALTER TABLE users DROP COLUMN IF EXISTS referred_at;
+ALTER TABLE users DROP COLUMN IF EXISTS referred_by;
+ALTER TABLE users DROP COLUMN IF EXISTS referral_code;
@@ -0,0 +1,42 @@
+-- Реферальные коды и привязка приглашённых игроков
+ALTER TABLE users ADD COLUMN IF NOT EXISTS referral_code TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS referred_by UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS referred_at TIMESTAMPTZ;
+
+UPDATE users SET referral_code = substr(md5(id::text), 1, 10) WHERE referral_code IS NULL;
+ALTER TABLE users ALTER COLUMN referral_code SET DEFAULT substr(md5(gen_random_uuid()::text), 1, 10);
+ALTER TABLE users ALTER COLUMN referral_code SET NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_referral_code ON users(referral_code);
+CREATE INDEX IF NOT EXISTS idx_users_referred_by ON users(referred_by);
+
+-- Игровой баланс (монеты), отдельно от очков
+CREATE TABLE IF NOT EXISTS balances (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    balance DECIMAL(20,2) NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Журнал начислений: (user_id, kind, reference) делает начисления идемпотентными
+CREATE TABLE IF NOT EXISTS balance_transactions (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    amount DECIMAL(20,2) NOT NULL,
+    kind TEXT NOT NULL,
+    reference TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, kind, reference)
+);
+
+-- Реферальные выплаты: бонус за приглашение и проценты с заработка приглашённых
+CREATE TABLE IF NOT EXISTS referral_rewards (
+    id UUID PRIMARY KEY,
+    referrer_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    referee_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    level INT NOT NULL,
+    amount DECIMAL(20,2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_balance_transactions_user_id ON balance_transactions(user_id);
+CREATE INDEX IF NOT EXISTS idx_referral_rewards_referrer_id ON referral_rewards(referrer_id);
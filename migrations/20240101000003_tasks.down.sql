@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS user_tasks;
+DROP TABLE IF EXISTS tasks;
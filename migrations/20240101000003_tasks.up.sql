@@ -0,0 +1,34 @@
+-- Каталог заданий (квестов)
+-- kind: join_channel | visit_link | invite_friends | reach_score | daily_checkin
+-- params: параметры проверки, например {"chat_id": "@channel"} или {"score": 10000}
+CREATE TABLE IF NOT EXISTS tasks (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    reward DECIMAL(20,2) NOT NULL DEFAULT 0,
+    params JSONB NOT NULL DEFAULT '{}',
+    sort_order INT NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Состояние заданий игрока. period = '' для разовых заданий и дата для ежедневных
+CREATE TABLE IF NOT EXISTS user_tasks (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    period TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL DEFAULT 'started',
+    started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    completed_at TIMESTAMPTZ,
+    claimed_at TIMESTAMPTZ,
+    PRIMARY KEY (user_id, task_id, period)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_tasks_user_id ON user_tasks(user_id);
+
+INSERT INTO tasks (id, kind, title, description, reward, params, sort_order) VALUES
+    ('daily_checkin', 'daily_checkin', 'Daily check-in', 'Open the game every day', 100, '{}', 10),
+    ('invite_3_friends', 'invite_friends', 'Invite 3 friends', 'Share your invite link', 1500, '{"count": 3}', 20),
+    ('reach_10k', 'reach_score', 'Reach 10 000 points', NULL, 1000, '{"score": 10000}', 30)
+ON CONFLICT (id) DO NOTHING;
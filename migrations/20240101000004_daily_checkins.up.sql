@@ -0,0 +1,9 @@
+-- Ежедневные отметки. Первичный ключ (user_id, day) не даёт получить награду дважды за день
+CREATE TABLE IF NOT EXISTS daily_checkins (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    streak INT NOT NULL,
+    reward DECIMAL(20,2) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, day)
+);
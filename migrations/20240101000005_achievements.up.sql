@@ -0,0 +1,31 @@
+-- Правила достижений. Новое достижение добавляется строкой в таблицу, без деплоя.
+-- metric: score | daily_streak | referrals | withdrawals; открывается, когда значение >= threshold
+CREATE TABLE IF NOT EXISTS achievements (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT,
+    metric TEXT NOT NULL,
+    threshold BIGINT NOT NULL,
+    reward DECIMAL(20,2) NOT NULL DEFAULT 0,
+    sort_order INT NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS user_achievements (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    achievement_id TEXT NOT NULL REFERENCES achievements(id) ON DELETE CASCADE,
+    reward DECIMAL(20,2) NOT NULL DEFAULT 0,
+    unlocked_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, achievement_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_achievements_metric ON achievements(metric, threshold);
+
+INSERT INTO achievements (id, title, description, metric, threshold, reward, sort_order) VALUES
+    ('first_tap', 'First tap', 'Make your first tap', 'score', 1, 50, 10),
+    ('score_10k', '10K club', 'Reach 10 000 points', 'score', 10000, 1000, 20),
+    ('streak_7', 'Week streak', 'Check in 7 days in a row', 'daily_streak', 7, 1000, 30),
+    ('referrals_5', 'Recruiter', 'Invite 5 friends', 'referrals', 5, 2500, 40),
+    ('first_withdrawal', 'First withdrawal', 'Complete your first claim', 'withdrawals', 1, 500, 50)
+ON CONFLICT (id) DO NOTHING;
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS passive_income;
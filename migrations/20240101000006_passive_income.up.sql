@@ -0,0 +1,6 @@
+-- Пассивный доход: уровень улучшения и момент последнего начисления
+CREATE TABLE IF NOT EXISTS passive_income (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    level INT NOT NULL DEFAULT 0,
+    last_accrued_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
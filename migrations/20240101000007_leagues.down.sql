@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS league_promotions;
+DROP TABLE IF EXISTS user_leagues;
@@ -0,0 +1,16 @@
+-- Текущая лига игрока (пороги лиг задаются в конфигурации)
+CREATE TABLE IF NOT EXISTS user_leagues (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    league TEXT NOT NULL,
+    previous_league TEXT,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Награды за повышение: первичный ключ гарантирует одну награду на лигу
+CREATE TABLE IF NOT EXISTS league_promotions (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    league TEXT NOT NULL,
+    reward DECIMAL(20,2) NOT NULL DEFAULT 0,
+    promoted_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, league)
+);
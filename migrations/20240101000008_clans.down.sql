@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS clan_membership_history;
+DROP TABLE IF EXISTS clan_members;
+DROP TABLE IF EXISTS clans;
@@ -0,0 +1,30 @@
+-- Кланы (команды), часто привязанные к Telegram-каналу
+CREATE TABLE IF NOT EXISTS clans (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    telegram_channel TEXT,
+    owner_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_clans_name ON clans(lower(name));
+
+-- Текущее членство: игрок состоит не больше чем в одном клане
+CREATE TABLE IF NOT EXISTS clan_members (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    clan_id UUID NOT NULL REFERENCES clans(id) ON DELETE CASCADE,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- История членства по сезонам: за сезон игрок может играть только за один клан
+CREATE TABLE IF NOT EXISTS clan_membership_history (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    clan_id UUID NOT NULL REFERENCES clans(id) ON DELETE CASCADE,
+    season TEXT NOT NULL,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    left_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_clan_members_clan_id ON clan_members(clan_id);
+CREATE INDEX IF NOT EXISTS idx_clan_membership_history_user ON clan_membership_history(user_id, season);
@@ -0,0 +1,39 @@
+-- Турниры: ограничены по времени, с платным входом и призами по местам
+-- prizes[i] — приз за (i+1)-е место
+CREATE TABLE IF NOT EXISTS tournaments (
+    id UUID PRIMARY KEY,
+    title TEXT NOT NULL,
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    entry_fee DECIMAL(20,2) NOT NULL DEFAULT 0,
+    min_league TEXT,
+    prizes DECIMAL(20,2)[] NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'open',
+    closed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (ends_at > starts_at)
+);
+
+-- Очки турнира считаются отдельно от scores.score: только прирост за время турнира
+CREATE TABLE IF NOT EXISTS tournament_entries (
+    tournament_id UUID NOT NULL REFERENCES tournaments(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    score BIGINT NOT NULL DEFAULT 0,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (tournament_id, user_id)
+);
+
+-- Архив итогов закрытых турниров
+CREATE TABLE IF NOT EXISTS tournament_results (
+    tournament_id UUID NOT NULL REFERENCES tournaments(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    rank INT NOT NULL,
+    score BIGINT NOT NULL,
+    prize DECIMAL(20,2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (tournament_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tournaments_status ON tournaments(status, ends_at);
+CREATE INDEX IF NOT EXISTS idx_tournament_entries_user_id ON tournament_entries(user_id);
@@ -0,0 +1,3 @@
+ALTER TABLE users DROP COLUMN IF EXISTS wallet_connected_at;
+ALTER TABLE users DROP COLUMN IF EXISTS wallet_address;
+DROP TABLE IF EXISTS user_energy;
@@ -0,0 +1,10 @@
+-- Энергия: значение на момент updated_at, восстановление считается лениво
+CREATE TABLE IF NOT EXISTS user_energy (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    energy INT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Кошелёк для вывода токенов
+ALTER TABLE users ADD COLUMN IF NOT EXISTS wallet_address TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS wallet_connected_at TIMESTAMPTZ;
@@ -0,0 +1,15 @@
+-- История переходов статуса заявок; id — номер события для SSE (Last-Event-ID)
+CREATE TABLE IF NOT EXISTS claim_status_events (
+    id BIGSERIAL PRIMARY KEY,
+    claim_id UUID NOT NULL REFERENCES claims(id) ON DELETE CASCADE,
+    status TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_claim_status_events_claim_id ON claim_status_events(claim_id, id);
+
+-- Текущий статус заявок, созданных до появления истории
+INSERT INTO claim_status_events (claim_id, status, created_at)
+SELECT c.id, COALESCE(c.status, 'pending'), COALESCE(c.created_at, now())
+FROM claims c
+WHERE NOT EXISTS (SELECT 1 FROM claim_status_events e WHERE e.claim_id = c.id);
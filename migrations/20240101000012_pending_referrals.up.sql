@@ -0,0 +1,6 @@
+-- Реферальный payload из /start в боте: применяется при первом входе в Mini App
+CREATE TABLE IF NOT EXISTS pending_referrals (
+    telegram_id BIGINT PRIMARY KEY,
+    start_param TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,23 @@
+-- Разрешил ли игрок боту писать в личку (initData allows_write_to_pm или /start в боте)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS allows_write_to_pm BOOLEAN NOT NULL DEFAULT false;
+
+-- Outbox уведомлений: пишется в транзакции вместе с событием, отправляется фоновой задачей.
+-- (user_id, kind, dedupe_key) не даёт отправить одно уведомление дважды
+CREATE TABLE IF NOT EXISTS notifications (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    dedupe_key TEXT NOT NULL,
+    text TEXT NOT NULL,
+    reply_markup JSONB,
+    -- pending | sent | skipped | failed
+    status TEXT NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    sent_at TIMESTAMPTZ,
+    UNIQUE (user_id, kind, dedupe_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_pending ON notifications(next_attempt_at) WHERE status = 'pending';
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS star_orders;
+DROP TABLE IF EXISTS shop_items;
@@ -0,0 +1,34 @@
+-- Товары за Telegram Stars. kind: balance — начислить amount на баланс, energy — восстановить энергию
+CREATE TABLE IF NOT EXISTS shop_items (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    price_stars INT NOT NULL CHECK (price_stars > 0),
+    amount DECIMAL(20,2) NOT NULL DEFAULT 0,
+    sort_order INT NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Заказы; id заказа передаётся в инвойс как payload.
+-- status: created | paid | refunded
+CREATE TABLE IF NOT EXISTS star_orders (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    item_id TEXT NOT NULL REFERENCES shop_items(id),
+    price_stars INT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'created',
+    telegram_payment_charge_id TEXT UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    paid_at TIMESTAMPTZ,
+    refunded_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_star_orders_user_id ON star_orders(user_id);
+
+INSERT INTO shop_items (id, kind, title, description, price_stars, amount, sort_order) VALUES
+    ('energy_refill', 'energy', 'Energy refill', 'Instantly restore full energy', 10, 0, 10),
+    ('coins_10k', 'balance', '10 000 coins', 'Add 10 000 coins to your balance', 50, 10000, 20),
+    ('coins_50k', 'balance', '50 000 coins', 'Add 50 000 coins to your balance', 200, 50000, 30)
+ON CONFLICT (id) DO NOTHING;
@@ -0,0 +1,13 @@
+ALTER TABLE claims DROP CONSTRAINT IF EXISTS claims_status_valid;
+ALTER TABLE claims DROP CONSTRAINT IF EXISTS claims_amount_positive;
+ALTER TABLE claims ALTER COLUMN created_at DROP NOT NULL;
+ALTER TABLE claims ALTER COLUMN status DROP NOT NULL;
+ALTER TABLE claims ALTER COLUMN created_at TYPE TIMESTAMP USING created_at AT TIME ZONE 'UTC';
+
+ALTER TABLE scores DROP CONSTRAINT IF EXISTS scores_score_non_negative;
+ALTER TABLE scores ALTER COLUMN updated_at DROP NOT NULL;
+ALTER TABLE scores ALTER COLUMN score DROP NOT NULL;
+ALTER TABLE scores ALTER COLUMN updated_at TYPE TIMESTAMP USING updated_at AT TIME ZONE 'UTC';
+
+ALTER TABLE users ALTER COLUMN created_at DROP NOT NULL;
+ALTER TABLE users ALTER COLUMN created_at TYPE TIMESTAMP USING created_at AT TIME ZONE 'UTC';
@@ -0,0 +1,36 @@
+-- Базовые таблицы: время с часовым поясом, NOT NULL и проверки значений.
+-- Старые значения TIMESTAMP записывались в UTC (DEFAULT now() на сервере в UTC).
+DO $$
+BEGIN
+    IF (SELECT data_type FROM information_schema.columns
+        WHERE table_name = 'users' AND column_name = 'created_at') = 'timestamp without time zone' THEN
+        ALTER TABLE users ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at AT TIME ZONE 'UTC';
+    END IF;
+    IF (SELECT data_type FROM information_schema.columns
+        WHERE table_name = 'scores' AND column_name = 'updated_at') = 'timestamp without time zone' THEN
+        ALTER TABLE scores ALTER COLUMN updated_at TYPE TIMESTAMPTZ USING updated_at AT TIME ZONE 'UTC';
+    END IF;
+    IF (SELECT data_type FROM information_schema.columns
+        WHERE table_name = 'claims' AND column_name = 'created_at') = 'timestamp without time zone' THEN
+        ALTER TABLE claims ALTER COLUMN created_at TYPE TIMESTAMPTZ USING created_at AT TIME ZONE 'UTC';
+    END IF;
+END $$;
+
+UPDATE users SET created_at = now() WHERE created_at IS NULL;
+ALTER TABLE users ALTER COLUMN created_at SET NOT NULL;
+
+UPDATE scores SET score = 0 WHERE score IS NULL;
+UPDATE scores SET updated_at = now() WHERE updated_at IS NULL;
+ALTER TABLE scores ALTER COLUMN score SET NOT NULL;
+ALTER TABLE scores ALTER COLUMN updated_at SET NOT NULL;
+ALTER TABLE scores DROP CONSTRAINT IF EXISTS scores_score_non_negative;
+ALTER TABLE scores ADD CONSTRAINT scores_score_non_negative CHECK (score >= 0);
+
+UPDATE claims SET status = 'pending' WHERE status IS NULL;
+UPDATE claims SET created_at = now() WHERE created_at IS NULL;
+ALTER TABLE claims ALTER COLUMN status SET NOT NULL;
+ALTER TABLE claims ALTER COLUMN created_at SET NOT NULL;
+ALTER TABLE claims DROP CONSTRAINT IF EXISTS claims_amount_positive;
+ALTER TABLE claims ADD CONSTRAINT claims_amount_positive CHECK (amount > 0);
+ALTER TABLE claims DROP CONSTRAINT IF EXISTS claims_status_valid;
+ALTER TABLE claims ADD CONSTRAINT claims_status_valid CHECK (status IN ('pending', 'completed'));
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_clan_membership_history_clan;
+ALTER TABLE clan_membership_history DROP COLUMN IF EXISTS score;
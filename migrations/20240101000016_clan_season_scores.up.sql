@@ -0,0 +1,5 @@
+-- Очки, набранные игроком за клан в пределах записи истории (членство в одном сезоне).
+-- Счёт клана за сезон — сумма по записям сезона, включая вышедших участников.
+ALTER TABLE clan_membership_history ADD COLUMN IF NOT EXISTS score BIGINT NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_clan_membership_history_clan ON clan_membership_history(clan_id, season);
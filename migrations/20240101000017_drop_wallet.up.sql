@@ -0,0 +1,3 @@
+-- Кошелёк никто не подключает: колонки не записываются
+ALTER TABLE users DROP COLUMN IF EXISTS wallet_connected_at;
+ALTER TABLE users DROP COLUMN IF EXISTS wallet_address;
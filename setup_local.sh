@@ -29,10 +29,8 @@ echo ""
 echo "ИЛИ выполните через терминал (если psql доступен):"
 echo "   createdb -U anymacstore alien_game"
 echo ""
-echo "6. После создания БД, примените миграции через pgAdmin:"
-echo "   - Правой кнопкой на базу 'alien_game' -> 'Query Tool'"
-echo "   - Скопируйте содержимое файла: migrations/20240101000001_create_tables.sql"
-echo "   - Вставьте в Query Tool и нажмите Execute (F5)"
+echo "6. Миграции применятся при первом запуске сервера (cargo run)"
+echo "   или вручную: cargo run -- migrate up"
 echo ""
 
 read -p "Создали базу данных alien_game? (y/n) " -n 1 -r
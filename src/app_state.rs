@@ -1,11 +1,28 @@
+use std::sync::Arc;
+
 use axum::extract::FromRef;
 use sqlx::PgPool;
+use tokio_util::sync::CancellationToken;
 use crate::config::Config;
+use crate::repos::{ClaimRepo, ScoreRepo, UserRepo};
+use crate::services::events::EventBus;
+use crate::services::health::Health;
+use crate::services::realtime::WsSessions;
+use crate::utils::bot_api::BotApi;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
     pub config: Config,
+    pub bot_api: BotApi,
+    pub ws_sessions: WsSessions,
+    pub events: EventBus,
+    pub health: Health,
+    /// Отменяется при остановке сервера: долгие соединения (WebSocket) закрываются сами
+    pub shutdown: CancellationToken,
+    pub users: Arc<dyn UserRepo>,
+    pub scores: Arc<dyn ScoreRepo>,
+    pub claims: Arc<dyn ClaimRepo>,
 }
 
 impl FromRef<AppState> for PgPool {
@@ -20,3 +37,31 @@ impl FromRef<AppState> for Config {
     }
 }
 
+#[cfg(test)]
+impl AppState {
+    /// Состояние для тестов обработчиков: репозитории в памяти, пул подключается лениво
+    /// и не используется, пока обработчик не пойдёт в базу напрямую
+    pub fn for_tests(repo: Arc<crate::repos::memory::InMemoryRepo>) -> Self {
+        // Окружение процесса не трогаем: тесты идут параллельно, остальное — значения по умолчанию
+        let config = Config::from_vars(|key| match key {
+            "DATABASE_URL" => Ok("postgres://localhost/unused".to_string()),
+            "TELEGRAM_BOT_TOKEN" => Ok("123:test".to_string()),
+            "JWT_SECRET" => Ok("test".to_string()),
+            _ => Err(std::env::VarError::NotPresent),
+        })
+        .unwrap();
+        
+        AppState {
+            pool: sqlx::postgres::PgPoolOptions::new().connect_lazy(&config.database_url).unwrap(),
+            bot_api: BotApi::from_config(&config),
+            ws_sessions: WsSessions::new(std::time::Duration::from_secs(1)),
+            events: EventBus::new(),
+            health: Health::new(),
+            shutdown: CancellationToken::new(),
+            users: repo.clone(),
+            scores: repo.clone(),
+            claims: repo,
+            config,
+        }
+    }
+}
@@ -0,0 +1,312 @@
+// Админская утилита: подпись и разбор initData, выпуск JWT, тестовые игроки, сверка балансов
+// Запуск: cargo run --bin alien-tap-admin -- --help
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use alien_tap_backend::models::user::TelegramUser;
+use alien_tap_backend::repos::pg::PgRepo;
+use alien_tap_backend::repos::UserRepo;
+use alien_tap_backend::services::balance;
+use alien_tap_backend::utils::{jwt, telegram};
+use clap::{Args, Parser, Subcommand};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Parser)]
+#[command(name = "alien-tap-admin", about = "Admin tools for Alien Tap backend")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Sign or inspect Telegram WebApp initData
+    #[command(subcommand)]
+    Initdata(InitDataCommand),
+    /// Mint a JWT for an existing player
+    Jwt(JwtArgs),
+    /// Create test players
+    #[command(subcommand)]
+    Seed(SeedCommand),
+    /// Compare balances with the transaction ledger
+    Reconcile(ReconcileArgs),
+}
+
+#[derive(Subcommand)]
+enum InitDataCommand {
+    /// Produce a valid initData string for a player
+    Sign(SignArgs),
+    /// Check an initData string and explain why it fails
+    Verify(VerifyArgs),
+}
+
+#[derive(Args)]
+struct SignArgs {
+    /// Telegram id of the player
+    #[arg(long)]
+    user_id: i64,
+    #[arg(long, default_value = "Test")]
+    first_name: String,
+    #[arg(long)]
+    last_name: Option<String>,
+    #[arg(long)]
+    username: Option<String>,
+    /// Payload of t.me/<bot>?startapp=..., e.g. a referral code
+    #[arg(long)]
+    start_param: Option<String>,
+    /// Unix time of signing; defaults to now
+    #[arg(long)]
+    auth_date: Option<i64>,
+    #[arg(long, env = "TELEGRAM_BOT_TOKEN", hide_env_values = true)]
+    bot_token: String,
+}
+
+#[derive(Args)]
+struct VerifyArgs {
+    /// initData string; `-` reads it from stdin
+    init_data: String,
+    #[arg(long, env = "TELEGRAM_BOT_TOKEN", hide_env_values = true)]
+    bot_token: String,
+}
+
+#[derive(Args)]
+struct JwtArgs {
+    /// Internal player id
+    #[arg(long, conflicts_with = "telegram_id", required_unless_present = "telegram_id")]
+    user_id: Option<Uuid>,
+    /// Telegram id; the player is looked up in the database
+    #[arg(long)]
+    telegram_id: Option<i64>,
+    #[arg(long, env = "JWT_SECRET", hide_env_values = true)]
+    secret: String,
+}
+
+#[derive(Subcommand)]
+enum SeedCommand {
+    /// Create players with consecutive Telegram ids and descending scores
+    Users(SeedUsersArgs),
+}
+
+#[derive(Args)]
+struct SeedUsersArgs {
+    #[arg(long, default_value_t = 10)]
+    count: i64,
+    /// Telegram id of the first player
+    #[arg(long, default_value_t = 900_000_000)]
+    first_id: i64,
+    /// Score of the first player; the rest get evenly smaller scores.
+    /// Without it existing scores are left untouched
+    #[arg(long)]
+    max_score: Option<i32>,
+}
+
+#[derive(Args)]
+struct ReconcileArgs {
+    /// Rewrite mismatching balances with the ledger sum
+    #[arg(long)]
+    fix: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<ExitCode> {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Initdata(InitDataCommand::Sign(args)) => sign(args),
+        Command::Initdata(InitDataCommand::Verify(args)) => verify(args),
+        Command::Jwt(args) => mint_jwt(args).await,
+        Command::Seed(SeedCommand::Users(args)) => seed_users(args).await,
+        Command::Reconcile(args) => reconcile(args).await,
+    }
+}
+
+async fn connect() -> anyhow::Result<PgPool> {
+    let database_url = std::env::var("DATABASE_URL")
+        .map_err(|_| anyhow::anyhow!("DATABASE_URL is not set"))?;
+    Ok(PgPoolOptions::new().max_connections(1).connect(&database_url).await?)
+}
+
+fn sign(args: SignArgs) -> anyhow::Result<ExitCode> {
+    let mut user = serde_json::json!({
+        "id": args.user_id,
+        "first_name": args.first_name,
+        "allows_write_to_pm": true,
+    });
+    if let Some(last_name) = args.last_name {
+        user["last_name"] = last_name.into();
+    }
+    if let Some(username) = args.username {
+        user["username"] = username.into();
+    }
+    let user = user.to_string();
+    let auth_date = args.auth_date.unwrap_or_else(|| chrono::Utc::now().timestamp()).to_string();
+
+    let mut params = vec![("user", user.as_str()), ("auth_date", auth_date.as_str())];
+    if let Some(start_param) = args.start_param.as_deref() {
+        params.push(("start_param", start_param));
+    }
+
+    println!("{}", telegram::sign_init_data(&params, &args.bot_token));
+    Ok(ExitCode::SUCCESS)
+}
+
+fn verify(args: VerifyArgs) -> anyhow::Result<ExitCode> {
+    let init_data = if args.init_data == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        input.trim().to_string()
+    } else {
+        args.init_data
+    };
+
+    let pairs: Vec<(&str, &str)> = init_data.split('&').filter_map(|pair| pair.split_once('=')).collect();
+    let hash = pairs.iter().find(|(key, _)| *key == "hash").map(|(_, value)| *value);
+    let unsigned: Vec<(&str, &str)> = pairs.iter().copied().filter(|(key, _)| *key != "hash").collect();
+
+    println!("Parameters: {}", pairs.iter().map(|(key, _)| *key).collect::<Vec<_>>().join(", "));
+    if let Ok(check_string) = telegram::build_check_string(&unsigned) {
+        println!("Check string:\n{}\n", check_string);
+        println!("Expected hash: {}", hex::encode(telegram::webapp_signature(&check_string, &args.bot_token)));
+    }
+    println!("Received hash: {}", hash.unwrap_or("-"));
+
+    if let Some(auth_date) = telegram::parse_param_from_init_data(&init_data, "auth_date").and_then(|v| v.parse::<i64>().ok()) {
+        println!("auth_date: {} ({} s ago)", auth_date, chrono::Utc::now().timestamp() - auth_date);
+    }
+    match telegram::parse_user_from_init_data(&init_data) {
+        Ok(user) => println!("user.id: {}", user["id"]),
+        Err(e) => println!("user: {}", e),
+    }
+
+    match telegram::verify_telegram_auth_from_init_data(&init_data, &args.bot_token, false) {
+        Ok(_) => {
+            println!("\nOK: signature is valid");
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(e) => {
+            println!("\nFAIL: {}", e);
+            if let Some(hint) = hash.and_then(|hash| explain_mismatch(&unsigned, hash, &args.bot_token)) {
+                println!("Hint: {}", hint);
+            }
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+/// Ищет типичную ошибку подписывающей стороны, при которой hash всё же сходится
+fn explain_mismatch(unsigned: &[(&str, &str)], hash: &str, bot_token: &str) -> Option<&'static str> {
+    let matches = |digest: Vec<u8>| hex::encode(digest).eq_ignore_ascii_case(hash);
+
+    let without_signature: Vec<(&str, &str)> = unsigned.iter().copied().filter(|(key, _)| *key != "signature").collect();
+    if without_signature.len() < unsigned.len() {
+        if let Ok(check_string) = telegram::build_check_string(&without_signature) {
+            if matches(telegram::webapp_signature(&check_string, bot_token)) {
+                return Some("hash was computed without the `signature` field; since Bot API 8.0 it is part of the check string");
+            }
+        }
+    }
+
+    let mut raw = unsigned.to_vec();
+    raw.sort_by(|a, b| a.0.cmp(b.0));
+    let raw_check_string = raw.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join("\n");
+    if matches(telegram::webapp_signature(&raw_check_string, bot_token)) {
+        return Some("hash was computed over URL-encoded values; values must be decoded first");
+    }
+
+    // Схема Login Widget: secret_key = SHA256(bot_token) вместо HMAC("WebAppData", bot_token)
+    let check_string = telegram::build_check_string(unsigned).ok()?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(&Sha256::digest(bot_token.as_bytes()))
+        .expect("HMAC can take key of any size");
+    mac.update(check_string.as_bytes());
+    if matches(mac.finalize().into_bytes().to_vec()) {
+        return Some("hash uses the Login Widget secret SHA256(bot_token) instead of HMAC(\"WebAppData\", bot_token)");
+    }
+
+    Some("data was modified or signed with a different bot token")
+}
+
+async fn mint_jwt(args: JwtArgs) -> anyhow::Result<ExitCode> {
+    let user_id = match (args.user_id, args.telegram_id) {
+        (Some(user_id), _) => user_id,
+        (None, Some(telegram_id)) => {
+            let pool = connect().await?;
+            sqlx::query_scalar!("SELECT id FROM users WHERE telegram_id = $1", telegram_id)
+                .fetch_optional(&pool)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No player with telegram_id {}", telegram_id))?
+        }
+        (None, None) => unreachable!("clap requires --user-id or --telegram-id"),
+    };
+
+    println!("{}", jwt::create_jwt(&user_id.to_string(), &args.secret)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn seed_users(args: SeedUsersArgs) -> anyhow::Result<ExitCode> {
+    let pool = connect().await?;
+    let repo = PgRepo::new(pool.clone());
+
+    for i in 0..args.count {
+        let telegram_id = args.first_id + i;
+        let upserted = repo
+            .upsert_telegram(&TelegramUser {
+                id: telegram_id,
+                username: Some(format!("seed_{}", telegram_id)),
+                first_name: Some(format!("Seed {}", i + 1)),
+                last_name: None,
+                allows_write_to_pm: false,
+            })
+            .await?;
+
+        let state = if upserted.inserted { "created" } else { "updated" };
+        print!("{:<12} {} {:<8}", telegram_id, upserted.user.id, state);
+
+        if let Some(max_score) = args.max_score {
+            let score = (i64::from(max_score) * (args.count - i) / args.count) as i32;
+            sqlx::query!(
+                "UPDATE scores SET score = $2, updated_at = now() WHERE user_id = $1",
+                upserted.user.id,
+                score
+            )
+            .execute(&pool)
+            .await?;
+            print!(" score={}", score);
+        }
+        println!();
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn reconcile(args: ReconcileArgs) -> anyhow::Result<ExitCode> {
+    let pool = connect().await?;
+    let mut conn = pool.acquire().await?;
+
+    let mismatches = balance::find_mismatches(&mut conn).await?;
+    if mismatches.is_empty() {
+        println!("All balances match the ledger");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    println!("{:<36}  {:>14}  {:>14}", "user_id", "balance", "ledger");
+    for mismatch in &mismatches {
+        println!("{:<36}  {:>14}  {:>14}", mismatch.user_id, mismatch.balance, mismatch.ledger);
+    }
+
+    if !args.fix {
+        println!("{} mismatching balances; rerun with --fix to rewrite them from the ledger", mismatches.len());
+        return Ok(ExitCode::FAILURE);
+    }
+
+    for mismatch in &mismatches {
+        balance::reset_to_ledger(&mut conn, mismatch.user_id).await?;
+    }
+    println!("{} balances rewritten from the ledger", mismatches.len());
+    Ok(ExitCode::SUCCESS)
+}
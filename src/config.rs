@@ -1,6 +1,9 @@
 use dotenvy::dotenv;
+use rust_decimal::Decimal;
 use std::env;
 
+use crate::models::league::League;
+
 #[derive(Clone)]
 pub struct Config {
     pub database_url: String,
@@ -8,24 +11,221 @@ pub struct Config {
     pub jwt_secret: String,
     pub port: u16,
     pub dev_mode: bool,
+    /// Применять миграции при старте; иначе схемой управляет `alien-tap-backend migrate`
+    pub run_migrations: bool,
+    /// Токен для админских эндпоинтов (заголовок X-Admin-Token); без него /admin недоступен
+    pub admin_token: Option<String>,
+    /// Секрет webhook бота (заголовок X-Telegram-Bot-Api-Secret-Token); без него /bot/webhook недоступен
+    pub telegram_webhook_secret: Option<String>,
+    /// Базовый URL Telegram Bot API (можно направить на локальный мок)
+    pub telegram_api_base_url: String,
+    /// Таймаут запроса к Bot API: зависший Telegram не должен держать обработчики и воркеры
+    pub telegram_api_timeout_ms: u64,
+    /// Ссылка на Mini App (например, https://t.me/alien_tap_bot/app) для инвайт-ссылок
+    pub telegram_webapp_url: Option<String>,
+    pub referral_bonus_referrer: Decimal,
+    pub referral_bonus_referee: Decimal,
+    /// Проценты с заработка приглашённых по уровням: [уровень 1, уровень 2, ...]
+    pub referral_level_percents: Vec<Decimal>,
+    /// Часовой пояс, по которому наступает новый игровой день
+    pub daily_timezone: chrono_tz::Tz,
+    /// Награды за ежедневный вход по дням серии; после последнего дня награда не растёт
+    pub daily_rewards: Vec<Decimal>,
+    /// Пассивный доход в час по уровням; уровень 0 — без дохода
+    pub passive_income_per_hour: Vec<Decimal>,
+    /// Стоимость перехода на следующий уровень: [0 -> 1, 1 -> 2, ...]
+    pub passive_upgrade_costs: Vec<Decimal>,
+    /// Сколько часов отсутствия максимум оплачивается пассивным доходом
+    pub passive_income_max_hours: i64,
+    /// Лиги по возрастанию порога очков
+    pub leagues: Vec<League>,
+    pub clan_max_members: i64,
+    /// Как часто фоновая задача закрывает завершившиеся турниры
+    pub tournament_close_interval_secs: u64,
+    /// Запас энергии: очко прироста счёта тратит единицу энергии
+    pub energy_max: i32,
+    pub energy_regen_per_second: i32,
+    /// Сколько исходящих сообщений может ждать отправки в одном WebSocket-соединении
+    pub ws_send_buffer: usize,
+    /// Как часто соединение перечитывает состояние из базы на случай изменений в обход шины событий
+    pub ws_poll_interval_secs: u64,
+    /// Сколько секунд после обрыва соединения можно продолжить сессию по resume-токену
+    pub ws_resume_ttl_secs: u64,
+    /// Как часто фоновая задача разбирает outbox уведомлений
+    pub notification_poll_interval_secs: u64,
+    pub notification_max_attempts: i32,
+    /// Минимальный интервал между сообщениями в один чат
+    pub notification_chat_interval_ms: u64,
+    /// Общий лимит сообщений бота в секунду
+    pub notification_rate_per_second: u32,
+    /// Таймаут проверки базы в /health/ready
+    pub health_db_timeout_ms: u64,
+    /// Сколько секунд после сигнала остановки /health/ready отвечает 503, а запросы ещё обслуживаются
+    pub drain_delay_secs: u64,
+    /// Сколько секунд после прекращения приёма соединений ждать текущие запросы и фоновые задачи
+    pub shutdown_timeout_secs: u64,
+    /// Порт /metrics для Prometheus; None — метрики не отдаются
+    pub metrics_port: Option<u16>,
 }
 
 impl Config {
     pub fn from_env() -> Result<Self, env::VarError> {
         dotenv().ok(); // Загружаем .env, но не падаем если его нет
-        
+
+        Self::from_vars(|key| env::var(key))
+    }
+
+    /// Конфигурация из произвольного источника переменных; тесты передают свои значения,
+    /// не трогая окружение процесса
+    pub fn from_vars(var: impl Fn(&str) -> Result<String, env::VarError>) -> Result<Self, env::VarError> {
         Ok(Config {
-            database_url: env::var("DATABASE_URL")?,
-            telegram_bot_token: env::var("TELEGRAM_BOT_TOKEN")?,
-            jwt_secret: env::var("JWT_SECRET")?,
-            port: env::var("PORT")
+            database_url: var("DATABASE_URL")?,
+            telegram_bot_token: var("TELEGRAM_BOT_TOKEN")?,
+            jwt_secret: var("JWT_SECRET")?,
+            port: var("PORT")
                 .unwrap_or_else(|_| "8000".to_string())
                 .parse()
                 .unwrap_or(8000),
-            dev_mode: env::var("DEV_MODE")
+            dev_mode: var("DEV_MODE")
                 .unwrap_or_else(|_| "false".to_string())
                 .parse()
                 .unwrap_or(false),
+            run_migrations: var("RUN_MIGRATIONS")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            admin_token: var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty()),
+            telegram_webhook_secret: var("TELEGRAM_WEBHOOK_SECRET").ok().filter(|secret| !secret.is_empty()),
+            telegram_api_base_url: var("TELEGRAM_API_BASE_URL")
+                .unwrap_or_else(|_| "https://api.telegram.org".to_string()),
+            telegram_api_timeout_ms: var("TELEGRAM_API_TIMEOUT_MS")
+                .unwrap_or_else(|_| "10000".to_string())
+                .parse()
+                .unwrap_or(10000),
+            telegram_webapp_url: var("TELEGRAM_WEBAPP_URL").ok(),
+            referral_bonus_referrer: var("REFERRAL_BONUS_REFERRER")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .unwrap_or(Decimal::from(500)),
+            referral_bonus_referee: var("REFERRAL_BONUS_REFEREE")
+                .unwrap_or_else(|_| "250".to_string())
+                .parse()
+                .unwrap_or(Decimal::from(250)),
+            referral_level_percents: parse_list(
+                &var("REFERRAL_LEVEL_PERCENTS").unwrap_or_else(|_| "10,5,2".to_string()),
+            ),
+            daily_timezone: var("DAILY_TIMEZONE")
+                .unwrap_or_else(|_| "UTC".to_string())
+                .parse()
+                .unwrap_or(chrono_tz::UTC),
+            daily_rewards: parse_list(
+                &var("DAILY_REWARDS").unwrap_or_else(|_| "100,200,300,500,800,1200,2000".to_string()),
+            ),
+            passive_income_per_hour: parse_list(
+                &var("PASSIVE_INCOME_PER_HOUR").unwrap_or_else(|_| "0,100,250,500,1000,2000".to_string()),
+            ),
+            passive_upgrade_costs: parse_list(
+                &var("PASSIVE_UPGRADE_COSTS").unwrap_or_else(|_| "500,1500,4000,10000,25000".to_string()),
+            ),
+            passive_income_max_hours: var("PASSIVE_INCOME_MAX_HOURS")
+                .unwrap_or_else(|_| "3".to_string())
+                .parse()
+                .unwrap_or(3),
+            leagues: parse_leagues(
+                &var("LEAGUES").unwrap_or_else(|_| {
+                    "Bronze:0,Silver:1000:200,Gold:10000:1000,Platinum:50000:5000,Diamond:200000:20000".to_string()
+                }),
+            ),
+            clan_max_members: var("CLAN_MAX_MEMBERS")
+                .unwrap_or_else(|_| "50".to_string())
+                .parse()
+                .unwrap_or(50),
+            tournament_close_interval_secs: var("TOURNAMENT_CLOSE_INTERVAL_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            energy_max: var("ENERGY_MAX")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()
+                .unwrap_or(1000),
+            energy_regen_per_second: var("ENERGY_REGEN_PER_SECOND")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .unwrap_or(1),
+            ws_send_buffer: var("WS_SEND_BUFFER")
+                .unwrap_or_else(|_| "32".to_string())
+                .parse()
+                .unwrap_or(32),
+            ws_poll_interval_secs: var("WS_POLL_INTERVAL_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            ws_resume_ttl_secs: var("WS_RESUME_TTL_SECS")
+                .unwrap_or_else(|_| "120".to_string())
+                .parse()
+                .unwrap_or(120),
+            notification_poll_interval_secs: var("NOTIFICATION_POLL_INTERVAL_SECS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap_or(5),
+            notification_max_attempts: var("NOTIFICATION_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap_or(5),
+            notification_chat_interval_ms: var("NOTIFICATION_CHAT_INTERVAL_MS")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()
+                .unwrap_or(1000),
+            notification_rate_per_second: var("NOTIFICATION_RATE_PER_SECOND")
+                .unwrap_or_else(|_| "25".to_string())
+                .parse()
+                .unwrap_or(25),
+            health_db_timeout_ms: var("HEALTH_DB_TIMEOUT_MS")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()
+                .unwrap_or(1000),
+            drain_delay_secs: var("DRAIN_DELAY_SECS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap_or(5),
+            shutdown_timeout_secs: var("SHUTDOWN_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .unwrap_or(30),
+            metrics_port: match var("METRICS_PORT") {
+                Ok(port) if port.is_empty() => None,
+                Ok(port) => port.parse().ok(),
+                Err(_) => Some(9100),
+            },
         })
     }
 }
+
+/// LOG_FORMAT=json включает JSON-логи; читается до `Config::from_env`, чтобы логировать его ошибки
+pub fn log_json() -> bool {
+    env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"))
+}
+
+/// Разбирает список значений через запятую, пропуская некорректные элементы
+fn parse_list<T: std::str::FromStr>(value: &str) -> Vec<T> {
+    value
+        .split(',')
+        .filter_map(|item| item.trim().parse().ok())
+        .collect()
+}
+
+/// Разбирает лиги `Name:min_score[:reward]` через запятую и сортирует по порогу.
+/// Первая лига всегда начинается с 0 очков; без лиг в конфигурации остаётся одна лига.
+fn parse_leagues(value: &str) -> Vec<League> {
+    let mut leagues: Vec<League> = value.split(',').filter_map(League::parse).collect();
+    leagues.sort_by_key(|league| league.min_score);
+    match leagues.first_mut() {
+        Some(first) => first.min_score = 0,
+        None => leagues.push(League {
+            name: "Bronze".to_string(),
+            min_score: 0,
+            reward: Decimal::ZERO,
+        }),
+    }
+    leagues
+}
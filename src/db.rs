@@ -1,7 +1,14 @@
+use std::collections::HashSet;
+
+use anyhow::bail;
+use sqlx::migrate::Migrator;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use crate::config::Config;
 
+/// Миграции из `migrations/`, встроенные в бинарник
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
 pub async fn create_pool(config: &Config) -> Result<PgPool, sqlx::Error> {
     let pool = PgPoolOptions::new()
         .max_connections(5)
@@ -10,3 +17,91 @@ pub async fn create_pool(config: &Config) -> Result<PgPool, sqlx::Error> {
     
     Ok(pool)
 }
+
+/// Версии успешно применённых миграций; пустой список, если sqlx ещё не запускался на этой базе
+async fn applied_versions(pool: &PgPool) -> Result<Vec<i64>, sqlx::Error> {
+    let tracked: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !tracked {
+        return Ok(Vec::new());
+    }
+    
+    sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success ORDER BY version")
+        .fetch_all(pool)
+        .await
+}
+
+/// Расхождение схемы базы с миграциями, встроенными в бинарник
+pub struct SchemaStatus {
+    /// Известные бинарнику, но не применённые миграции
+    pub pending: usize,
+    /// Применённые миграции, о которых бинарник не знает (базу обновила более свежая версия)
+    pub unknown: Vec<i64>,
+}
+
+pub async fn schema_status(pool: &PgPool) -> Result<SchemaStatus, sqlx::Error> {
+    let known: HashSet<i64> = MIGRATOR.iter().map(|m| m.version).collect();
+    let applied = applied_versions(pool).await?;
+    
+    let unknown: Vec<i64> = applied.iter().copied().filter(|v| !known.contains(v)).collect();
+    let pending = known.iter().filter(|v| !applied.contains(v)).count();
+    Ok(SchemaStatus { pending, unknown })
+}
+
+/// Отказывается запускаться со схемой, в которой есть миграции новее, чем знает бинарник.
+/// Возвращает число неприменённых миграций.
+pub async fn ensure_schema_compatible(pool: &PgPool) -> anyhow::Result<usize> {
+    let status = schema_status(pool).await?;
+    if !status.unknown.is_empty() {
+        bail!(
+            "Database schema is newer than this build: unknown migrations {:?}. Deploy a newer version or run `migrate down` from it",
+            status.unknown
+        );
+    }
+    
+    Ok(status.pending)
+}
+
+/// `alien-tap-backend migrate <up|status|down [VERSION]>`.
+/// `down` откатывает последнюю миграцию, `down VERSION` — все миграции новее VERSION.
+pub async fn run_migrate_command(args: &[String]) -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    let database_url = std::env::var("DATABASE_URL")
+        .map_err(|_| anyhow::anyhow!("DATABASE_URL is not set"))?;
+    let pool = PgPoolOptions::new().max_connections(1).connect(&database_url).await?;
+    
+    match args.first().map(String::as_str) {
+        Some("up") => {
+            MIGRATOR.run(&pool).await?;
+            println!("Migrations applied");
+        }
+        Some("status") => {
+            let applied: HashSet<i64> = applied_versions(&pool).await?.into_iter().collect();
+            for migration in MIGRATOR.iter() {
+                let state = if applied.contains(&migration.version) { "applied" } else { "pending" };
+                println!("{:<16} {:<8} {}", migration.version, state, migration.description);
+            }
+            for version in applied.iter().filter(|v| !MIGRATOR.iter().any(|m| m.version == **v)) {
+                println!("{:<16} {:<8} (unknown to this build)", version, "applied");
+            }
+        }
+        Some("down") => {
+            let target = match args.get(1) {
+                Some(version) => version.parse()?,
+                None => {
+                    let applied = applied_versions(&pool).await?;
+                    if applied.is_empty() {
+                        bail!("No applied migrations to revert");
+                    }
+                    applied.iter().rev().nth(1).copied().unwrap_or(0)
+                }
+            };
+            MIGRATOR.undo(&pool, target).await?;
+            println!("Reverted migrations newer than {}", target);
+        }
+        _ => bail!("Usage: alien-tap-backend migrate <up|status|down [VERSION]>"),
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,92 @@
+pub mod app_state;
+pub mod config;
+pub mod db;
+pub mod models;
+pub mod repos;
+pub mod routes;
+pub mod services;
+pub mod utils;
+
+use axum::{
+    http::Method,
+    Router,
+};
+use tower::ServiceBuilder;
+use tower_http::cors::{Any, CorsLayer};
+use tower_http::trace::TraceLayer;
+use tower_http::classify::ServerErrorsFailureClass;
+
+use crate::app_state::AppState;
+
+/// Полный роутер приложения со всеми слоями; используется сервером и интеграционными тестами
+pub fn build_app(state: AppState) -> Router {
+    // Настройка CORS
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_headers(Any)
+        .expose_headers([utils::request_id::HEADER]);
+    
+    Router::new()
+        .nest("/health", routes::health::router())
+        .nest("/auth", routes::auth::router())
+        .nest("/me", routes::profile::router())
+        .nest("/game", routes::game::router())
+        .nest("/claim", routes::claim::router())
+        .nest("/referrals", routes::referral::router())
+        .nest("/tasks", routes::task::router())
+        .nest("/daily", routes::daily::router())
+        .nest("/achievements", routes::achievement::router())
+        .nest("/clans", routes::clan::router())
+        .nest("/tournaments", routes::tournament::router())
+        .nest("/admin", routes::admin::router())
+        .nest("/ws", routes::realtime::router())
+        .nest("/bot", routes::bot::router())
+        .nest("/shop", routes::shop::router())
+        // Только для сопоставленных маршрутов: шаблон пути известен, 404 не плодят ряды метрик
+        .route_layer(axum::middleware::from_fn(services::metrics::track_http))
+        .layer(
+            ServiceBuilder::new()
+                // Снаружи трассировки: span читает id из заголовка запроса
+                .layer(axum::middleware::from_fn(utils::request_id::propagate))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(|request: &axum::http::Request<_>| {
+                            let request_id = request
+                                .headers()
+                                .get(&utils::request_id::HEADER)
+                                .and_then(|value| value.to_str().ok())
+                                .unwrap_or_default();
+                            tracing::info_span!(
+                                "http_request",
+                                method = %request.method(),
+                                // Без JWT из ?token= (WebSocket и SSE)
+                                uri = %utils::auth::redact_token(request.uri()),
+                                version = ?request.version(),
+                                request_id = %request_id,
+                                // Заполняется после проверки JWT
+                                user_id = tracing::field::Empty,
+                            )
+                        })
+                        .on_request(|request: &axum::http::Request<_>, _span: &tracing::Span| {
+                            tracing::info!(
+                                "→ {} {}",
+                                request.method(),
+                                request.uri().path()
+                            );
+                        })
+                        .on_response(|response: &axum::http::Response<_>, latency: std::time::Duration, _span: &tracing::Span| {
+                            tracing::info!(
+                                "← {} ({}ms)",
+                                response.status(),
+                                latency.as_millis()
+                            );
+                        })
+                        .on_failure(|error: ServerErrorsFailureClass, _latency: std::time::Duration, _span: &tracing::Span| {
+                            tracing::error!("✗ Request failed: {:?}", error);
+                        }),
+                )
+                .layer(cors)
+        )
+        .with_state(state)
+}
@@ -1,36 +1,42 @@
-mod app_state;
-mod config;
-mod db;
-mod models;
-mod routes;
-mod utils;
+use std::future::IntoFuture;
+use std::sync::Arc;
+use std::time::Duration;
 
-use axum::{
-    http::Method,
-    response::Json,
-    routing::get,
-    Router,
-};
-use serde_json::json;
-use tower::ServiceBuilder;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::trace::TraceLayer;
-use tower_http::classify::ServerErrorsFailureClass;
-use tracing_subscriber;
+use futures_util::future::join_all;
+use tokio_util::sync::CancellationToken;
 
-use crate::app_state::AppState;
-use config::Config;
-use db::create_pool;
+use alien_tap_backend::app_state::AppState;
+use alien_tap_backend::build_app;
+use alien_tap_backend::config::{self, Config};
+use alien_tap_backend::db::{self, create_pool};
+use alien_tap_backend::repos::pg::PgRepo;
+use alien_tap_backend::routes;
+use alien_tap_backend::services::{self, events::EventBus, health::Health, realtime::WsSessions};
+use alien_tap_backend::utils::bot_api::BotApi;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Инициализация логирования
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "alien_tap_backend=debug,tower_http=debug,axum=debug".into()),
-        )
-        .init();
+    // Инициализация логирования; идёт раньше Config, поэтому .env читаем здесь
+    dotenvy::dotenv().ok();
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "alien_tap_backend=debug,tower_http=debug,axum=debug".into());
+    if config::log_json() {
+        // Одна JSON-строка на событие; поля span запроса (request_id, user_id) — в "span"
+        tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_span_list(false)
+            .with_env_filter(filter)
+            .init();
+    } else {
+        tracing_subscriber::fmt().with_env_filter(filter).init();
+    }
+    
+    // alien-tap-backend migrate <up|status|down>
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return db::run_migrate_command(&args[1..]).await;
+    }
     
     // Загрузка конфигурации
     let config = Config::from_env()
@@ -38,72 +44,158 @@ async fn main() -> anyhow::Result<()> {
     
     tracing::info!("Starting server on port {}", config.port);
     
+    // Recorder ставится до первых запросов и фоновых задач, иначе их метрики потеряются
+    let metrics = services::metrics::install()?;
+    
     // Подключение к базе данных
     let pool = create_pool(&config).await?;
     tracing::info!("Connected to database");
     
-    // Настройка CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
-        .allow_headers(Any);
+    // Схема: не стартуем на базе новее бинарника, миграции применяем по RUN_MIGRATIONS
+    let pending = db::ensure_schema_compatible(&pool).await?;
+    if config.run_migrations {
+        db::MIGRATOR.run(&pool).await?;
+        tracing::info!("Database migrations applied");
+    } else if pending > 0 {
+        tracing::warn!("{} migrations are not applied; run `alien-tap-backend migrate up`", pending);
+    }
+    
+    // Пульс фоновых задач и флаг остановки для /health/ready
+    let health = Health::new();
+    
+    // Отменяется после сигнала остановки: сервер перестаёт принимать соединения, фоновые задачи выходят
+    let shutdown = CancellationToken::new();
+    let mut workers = Vec::new();
+    
+    // Фоновая задача: закрытие турниров и выплата призов
+    workers.push(tokio::spawn(services::tournaments::run_payout_worker(
+        pool.clone(),
+        config.clone(),
+        health.clone(),
+        shutdown.clone(),
+    )));
+    
+    // Фоновая задача: отправка уведомлений из outbox
+    let bot_api = BotApi::from_config(&config);
+    workers.push(tokio::spawn(services::notifications::run_outbox_worker(
+        pool.clone(),
+        config.clone(),
+        bot_api.clone(),
+        health.clone(),
+        shutdown.clone(),
+    )));
+    
+    // Шина событий между инстансами (LISTEN/NOTIFY)
+    let events = EventBus::new();
+    workers.push(tokio::spawn(events.clone().run_listener(pool.clone(), health.clone(), shutdown.clone())));
+    
+    // Метрики на отдельном порту, не опубликованном наружу
+    if let Some(metrics_port) = config.metrics_port {
+        let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", metrics_port)).await?;
+        tracing::info!("Metrics listening on http://0.0.0.0:{}/metrics", metrics_port);
+        let metrics_app = routes::metrics::router(metrics, pool.clone());
+        let shutdown = shutdown.clone();
+        workers.push(tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, metrics_app)
+                .with_graceful_shutdown(shutdown.cancelled_owned())
+                .await
+            {
+                tracing::error!("Metrics server failed: {}", e);
+            }
+        }));
+    }
     
     // Создание состояния приложения
+    let repo = Arc::new(PgRepo::new(pool.clone()));
     let app_state = AppState {
-        pool,
+        pool: pool.clone(),
         config: config.clone(),
+        bot_api,
+        ws_sessions: WsSessions::new(std::time::Duration::from_secs(config.ws_resume_ttl_secs)),
+        events,
+        health: health.clone(),
+        shutdown: shutdown.clone(),
+        users: repo.clone(),
+        scores: repo.clone(),
+        claims: repo,
     };
     
     // Создание роутера
-    let app = Router::new()
-        .route("/health", get(health))
-        .nest("/auth", routes::auth::router())
-        .nest("/game", routes::game::router())
-        .nest("/claim", routes::claim::router())
-        .layer(
-            ServiceBuilder::new()
-                .layer(
-                    TraceLayer::new_for_http()
-                        .make_span_with(|request: &axum::http::Request<_>| {
-                            tracing::info_span!(
-                                "http_request",
-                                method = %request.method(),
-                                uri = %request.uri(),
-                                version = ?request.version(),
-                            )
-                        })
-                        .on_request(|request: &axum::http::Request<_>, _span: &tracing::Span| {
-                            tracing::info!(
-                                "→ {} {}",
-                                request.method(),
-                                request.uri().path()
-                            );
-                        })
-                        .on_response(|response: &axum::http::Response<_>, latency: std::time::Duration, _span: &tracing::Span| {
-                            tracing::info!(
-                                "← {} ({}ms)",
-                                response.status(),
-                                latency.as_millis()
-                            );
-                        })
-                        .on_failure(|error: ServerErrorsFailureClass, _latency: std::time::Duration, _span: &tracing::Span| {
-                            tracing::error!("✗ Request failed: {:?}", error);
-                        }),
-                )
-                .layer(cors)
-        )
-        .with_state(app_state);
+    let app = build_app(app_state);
     
     // Запуск сервера
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", config.port)).await?;
     
     tracing::info!("Server listening on http://0.0.0.0:{}", config.port);
     
-    axum::serve(listener, app).await?;
+    let mut server = tokio::spawn(
+        axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+            .into_future(),
+    );
+    
+    tokio::select! {
+        _ = shutdown_signal() => {}
+        // Сам по себе сервер завершается только с ошибкой
+        served = &mut server => {
+            shutdown.cancel();
+            served??;
+            return Ok(());
+        }
+    }
     
+    // Readiness отвечает 503, но запросы ещё обслуживаются, пока балансировщик снимает инстанс с трафика
+    let drain_delay = Duration::from_secs(config.drain_delay_secs);
+    tracing::info!("Shutdown requested, draining for {:?}", drain_delay);
+    health.start_draining();
+    tokio::time::sleep(drain_delay).await;
+    
+    // Новые соединения больше не принимаются; текущие запросы и циклы фоновых задач дорабатывают
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    tracing::info!("Stopping, waiting up to {:?} for in-flight requests and background tasks", shutdown_timeout);
+    shutdown.cancel();
+    
+    let server_abort = server.abort_handle();
+    let worker_aborts: Vec<_> = workers.iter().map(|worker| worker.abort_handle()).collect();
+    let drained = tokio::time::timeout(shutdown_timeout, async {
+        let served = server.await;
+        join_all(workers).await;
+        served
+    })
+    .await;
+    
+    match drained {
+        Ok(served) => served??,
+        Err(_) => {
+            tracing::warn!("Shutdown timeout exceeded, aborting remaining requests and background tasks");
+            server_abort.abort();
+            worker_aborts.iter().for_each(|worker| worker.abort());
+        }
+    }
+    
+    pool.close().await;
+    tracing::info!("Server stopped");
     Ok(())
 }
 
-async fn health() -> Json<serde_json::Value> {
-    Json(json!({ "status": "ok" }))
+/// Ждёт SIGTERM или Ctrl+C
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.expect("failed to listen for Ctrl+C");
+    };
+    
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
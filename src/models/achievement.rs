@@ -0,0 +1,22 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct AchievementView {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub metric: String,
+    pub threshold: i64,
+    pub reward: Decimal,
+    pub unlocked: bool,
+    pub unlocked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Достижение, открытое в результате игрового события
+#[derive(Debug, Clone, Serialize)]
+pub struct UnlockedAchievement {
+    pub id: String,
+    pub title: String,
+    pub reward: Decimal,
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::TelegramUser;
+
+/// Входящее обновление Bot API; поля, которые бот не обрабатывает, игнорируются
+#[derive(Debug, Deserialize)]
+pub struct Update {
+    pub update_id: i64,
+    pub message: Option<Message>,
+    pub pre_checkout_query: Option<PreCheckoutQuery>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Message {
+    pub from: Option<TelegramUser>,
+    pub chat: Chat,
+    pub text: Option<String>,
+    pub successful_payment: Option<SuccessfulPayment>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Chat {
+    pub id: i64,
+    /// private | group | supergroup | channel
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+/// Подтверждение перед оплатой: на него нужно ответить в течение 10 секунд
+#[derive(Debug, Deserialize)]
+pub struct PreCheckoutQuery {
+    pub id: String,
+    pub from: TelegramUser,
+    pub currency: String,
+    pub total_amount: i64,
+    pub invoice_payload: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuccessfulPayment {
+    pub currency: String,
+    pub total_amount: i64,
+    pub invoice_payload: String,
+    pub telegram_payment_charge_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlineKeyboardMarkup {
+    pub inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlineKeyboardButton {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_app: Option<WebAppInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebAppInfo {
+    pub url: String,
+}
+
+/// Параметры sendMessage
+#[derive(Debug, Clone, Serialize)]
+pub struct SendMessage {
+    pub chat_id: i64,
+    pub text: String,
+    pub parse_mode: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<InlineKeyboardMarkup>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnswerPreCheckoutQuery {
+    pub pre_checkout_query_id: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LabeledPrice {
+    pub label: String,
+    pub amount: i64,
+}
+
+/// Параметры createInvoiceLink; для Telegram Stars currency = "XTR", provider_token пустой
+#[derive(Debug, Serialize)]
+pub struct CreateInvoiceLink {
+    pub title: String,
+    pub description: String,
+    pub payload: String,
+    pub provider_token: String,
+    pub currency: String,
+    pub prices: Vec<LabeledPrice>,
+}
+
+/// Ответ на webhook с вызовом метода: Telegram выполнит его без отдельного запроса к Bot API
+#[derive(Debug, Serialize)]
+#[serde(tag = "method")]
+pub enum WebhookReply {
+    #[serde(rename = "sendMessage")]
+    SendMessage(SendMessage),
+    #[serde(rename = "answerPreCheckoutQuery")]
+    AnswerPreCheckoutQuery(AnswerPreCheckoutQuery),
+}
+
+/// Отправленное сообщение (из ответа sendMessage нужен только id)
+#[derive(Debug, Deserialize)]
+pub struct SentMessage {
+    pub message_id: i64,
+}
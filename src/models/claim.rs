@@ -9,7 +9,6 @@ pub struct Claim {
     pub user_id: Uuid,
     pub amount: Decimal,
     pub status: String,
-    #[sqlx(default)]
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -22,3 +21,16 @@ pub struct CreateClaimRequest {
 pub struct ConfirmClaimRequest {
     pub claim_id: Uuid,
 }
+
+/// Данные SSE-события `status`
+#[derive(Debug, Serialize)]
+pub struct ClaimStatusUpdate {
+    pub claim_id: Uuid,
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClaimEventsQuery {
+    /// JWT токен: EventSource не умеет передавать заголовки
+    pub token: Option<String>,
+}
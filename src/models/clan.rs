@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct ClanSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub telegram_channel: Option<String>,
+    pub member_count: i64,
+    /// Очки, набранные участниками за клан в текущем сезоне (в том числе вышедшими)
+    pub score: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClanMember {
+    pub user_id: Uuid,
+    pub username: Option<String>,
+    pub first_name: Option<String>,
+    pub score: i32,
+    /// Вклад в счёт клана за текущий сезон
+    pub season_score: i64,
+    pub joined_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MembershipRecord {
+    pub clan_id: Uuid,
+    pub clan_name: String,
+    pub season: String,
+    pub joined_at: chrono::DateTime<chrono::Utc>,
+    pub left_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateClanRequest {
+    pub name: String,
+    pub telegram_channel: Option<String>,
+}
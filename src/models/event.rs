@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Событие шины: рассылается всем инстансам через Postgres NOTIFY
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AppEvent {
+    ClaimStatusChanged {
+        user_id: Uuid,
+        claim_id: Uuid,
+        /// Номер события в claim_status_events
+        event_id: i64,
+        status: String,
+    },
+    /// Счёт игрока вырос — его место в лидерборде могло измениться,
+    /// как и места игроков со счётом в [previous_score, score)
+    ScoreChanged {
+        user_id: Uuid,
+        score: i32,
+        /// Нет в событиях от инстансов старой версии
+        #[serde(default)]
+        previous_score: i32,
+    },
+}
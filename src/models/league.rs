@@ -0,0 +1,47 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// Лига из конфигурации: игрок попадает в неё, набрав min_score очков
+#[derive(Debug, Clone, Serialize)]
+pub struct League {
+    pub name: String,
+    pub min_score: i32,
+    /// Награда за первое повышение до этой лиги
+    pub reward: Decimal,
+}
+
+impl League {
+    /// Разбирает элемент вида `Name:min_score[:reward]`
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().split(':');
+        let name = parts.next()?.trim();
+        let min_score = parts.next()?.trim().parse().ok()?;
+        let reward = match parts.next() {
+            Some(reward) => reward.trim().parse().ok()?,
+            None => Decimal::ZERO,
+        };
+
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(League {
+            name: name.to_string(),
+            min_score,
+            reward,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeagueStatus {
+    pub league: String,
+    pub previous_league: Option<String>,
+    /// promoted | demoted, если лига изменилась в этом запросе
+    pub change: Option<String>,
+    pub changed_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub next_league: Option<String>,
+    pub next_league_score: Option<i32>,
+    /// Награда за повышение, начисленная в этом запросе
+    pub promotion_reward: Decimal,
+}
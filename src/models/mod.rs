@@ -1,3 +1,13 @@
 pub mod user;
 pub mod score;
+pub mod shop;
 pub mod claim;
+pub mod referral;
+pub mod task;
+pub mod achievement;
+pub mod bot;
+pub mod league;
+pub mod clan;
+pub mod tournament;
+pub mod event;
+pub mod realtime;
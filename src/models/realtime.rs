@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Сообщения клиента по WebSocket
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Пачка тапов; `seq` растёт от пачки к пачке и защищает от повторного применения после переподключения
+    Taps { seq: u64, count: i32 },
+}
+
+/// События сервера. Все, кроме `welcome`, нумеруются и повторяются после переподключения.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    Welcome {
+        resume_token: Uuid,
+        resumed: bool,
+        /// Последняя применённая пачка тапов: всё с меньшим или равным seq можно не переотправлять
+        last_tap_seq: u64,
+    },
+    TapsApplied {
+        seq: u64,
+        accepted: i32,
+        score: i32,
+        energy: i32,
+    },
+    Rank {
+        rank: i64,
+        previous_rank: Option<i64>,
+    },
+    ClaimStatus {
+        claim_id: Uuid,
+        status: String,
+    },
+    EnergyRefilled {
+        energy: i32,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventEnvelope {
+    /// Номер события в сессии; при переподключении передаётся как `last_event_id`
+    pub id: u64,
+    #[serde(flatten)]
+    pub event: ServerEvent,
+}
@@ -0,0 +1,19 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct Invitee {
+    pub user_id: Uuid,
+    pub username: Option<String>,
+    pub first_name: Option<String>,
+    pub joined_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Сколько пригласивший заработал с этого игрока (бонус + проценты 1-го уровня)
+    pub earned: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LevelEarnings {
+    pub level: i32,
+    pub amount: Decimal,
+}
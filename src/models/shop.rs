@@ -0,0 +1,28 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ShopItem {
+    pub id: String,
+    /// balance | energy
+    pub kind: String,
+    pub title: String,
+    pub description: String,
+    pub price_stars: i32,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvoiceResponse {
+    pub order_id: Uuid,
+    pub invoice_link: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RefundResponse {
+    pub success: bool,
+    /// Удалось ли списать начисленные монеты (игрок мог их уже потратить)
+    pub reverted: bool,
+}
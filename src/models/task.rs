@@ -0,0 +1,23 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Task {
+    pub id: String,
+    pub kind: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub reward: Decimal,
+    pub params: serde_json::Value,
+}
+
+/// Задание вместе с состоянием для конкретного игрока
+#[derive(Debug, Serialize)]
+pub struct TaskView {
+    #[serde(flatten)]
+    pub task: Task,
+    /// available | started | completed | claimed
+    pub status: String,
+    pub daily: bool,
+}
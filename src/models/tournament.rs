@@ -0,0 +1,41 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize)]
+pub struct Tournament {
+    pub id: Uuid,
+    pub title: String,
+    pub starts_at: chrono::DateTime<chrono::Utc>,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+    pub entry_fee: Decimal,
+    pub min_league: Option<String>,
+    /// prizes[i] — приз за (i+1)-е место
+    pub prizes: Vec<Decimal>,
+    /// open | closed
+    pub status: String,
+    pub closed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TournamentStanding {
+    pub rank: i64,
+    pub user_id: Uuid,
+    pub username: Option<String>,
+    pub first_name: Option<String>,
+    pub score: i64,
+    /// Для закрытых турниров — выплаченный приз
+    pub prize: Option<Decimal>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTournamentRequest {
+    pub title: String,
+    pub starts_at: chrono::DateTime<chrono::Utc>,
+    pub ends_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub entry_fee: Decimal,
+    pub min_league: Option<String>,
+    #[serde(default)]
+    pub prizes: Vec<Decimal>,
+}
@@ -19,4 +19,35 @@ pub struct TelegramUser {
     pub username: Option<String>,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    /// Есть только в initData Mini App
+    #[serde(default)]
+    pub allows_write_to_pm: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnergyStatus {
+    pub current: i32,
+    pub max: i32,
+    pub regen_per_second: i32,
+    pub seconds_to_full: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileLeague {
+    pub league: String,
+    pub next_league: Option<String>,
+    pub next_league_score: Option<i32>,
+}
+
+/// Профиль и состояние игры для главного экрана
+#[derive(Debug, Serialize)]
+pub struct Profile {
+    #[serde(flatten)]
+    pub user: User,
+    pub score: i32,
+    pub balance: rust_decimal::Decimal,
+    /// Место в общем лидерборде; None, пока у игрока нет счёта
+    pub rank: Option<i64>,
+    pub energy: EnergyStatus,
+    pub league: ProfileLeague,
 }
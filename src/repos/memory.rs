@@ -0,0 +1,203 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use super::{ClaimRepo, ScoreRepo, UpsertedUser, UserRepo};
+use crate::config::Config;
+use crate::models::achievement::UnlockedAchievement;
+use crate::models::claim::Claim;
+use crate::models::league::LeagueStatus;
+use crate::models::score::LeaderboardEntry;
+use crate::models::user::{TelegramUser, User};
+use crate::services::leagues;
+use crate::services::score::{ScoreOutcome, ScoreUpdate};
+
+#[derive(Default)]
+struct Data {
+    users: Vec<User>,
+    /// (user_id, score)
+    scores: Vec<(Uuid, i32)>,
+    claims: Vec<Claim>,
+    /// (telegram_id, start_param)
+    pending_referrals: Vec<(i64, String)>,
+}
+
+/// Хранилище в памяти для тестов обработчиков без Postgres
+#[derive(Default)]
+pub struct InMemoryRepo {
+    data: Mutex<Data>,
+}
+
+impl InMemoryRepo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_score(&self, user_id: Uuid, score: i32) {
+        let mut data = self.data.lock().unwrap();
+        data.scores.retain(|(id, _)| *id != user_id);
+        data.scores.push((user_id, score));
+    }
+
+    pub fn add_pending_referral(&self, telegram_id: i64, start_param: &str) {
+        self.data.lock().unwrap().pending_referrals.push((telegram_id, start_param.to_string()));
+    }
+
+    pub fn has_pending_referral(&self, telegram_id: i64) -> bool {
+        self.data.lock().unwrap().pending_referrals.iter().any(|(id, _)| *id == telegram_id)
+    }
+
+    pub fn claims(&self) -> Vec<Claim> {
+        self.data.lock().unwrap().claims.clone()
+    }
+}
+
+#[async_trait]
+impl UserRepo for InMemoryRepo {
+    async fn upsert_telegram(&self, telegram_user: &TelegramUser) -> Result<UpsertedUser, sqlx::Error> {
+        let mut data = self.data.lock().unwrap();
+
+        if let Some(user) = data.users.iter_mut().find(|u| u.telegram_id == telegram_user.id) {
+            user.username = telegram_user.username.clone();
+            user.first_name = telegram_user.first_name.clone();
+            user.last_name = telegram_user.last_name.clone();
+            return Ok(UpsertedUser {
+                user: user.clone(),
+                inserted: false,
+            });
+        }
+
+        let user = User {
+            id: Uuid::new_v4(),
+            telegram_id: telegram_user.id,
+            username: telegram_user.username.clone(),
+            first_name: telegram_user.first_name.clone(),
+            last_name: telegram_user.last_name.clone(),
+            created_at: Utc::now(),
+        };
+        data.users.push(user.clone());
+        data.scores.push((user.id, 0));
+
+        Ok(UpsertedUser { user, inserted: true })
+    }
+
+    /// Реферальных начислений в памяти нет: первый вход только забирает payload из /start
+    async fn login(
+        &self,
+        _config: &Config,
+        telegram_user: &TelegramUser,
+        _start_param: Option<&str>,
+    ) -> Result<UpsertedUser, sqlx::Error> {
+        let upserted = self.upsert_telegram(telegram_user).await?;
+        if upserted.inserted {
+            self.data.lock().unwrap().pending_referrals.retain(|(id, _)| *id != telegram_user.id);
+        }
+        Ok(upserted)
+    }
+}
+
+#[async_trait]
+impl ScoreRepo for InMemoryRepo {
+    async fn get(&self, user_id: Uuid) -> Result<i32, sqlx::Error> {
+        let data = self.data.lock().unwrap();
+        Ok(data.scores.iter().find(|(id, _)| *id == user_id).map(|(_, score)| *score).unwrap_or(0))
+    }
+
+    /// Меняется только счёт: энергия всегда полная, достижений, лиг в базе и начислений нет
+    async fn apply(&self, config: &Config, user_id: Uuid, update: ScoreUpdate) -> Result<ScoreOutcome, sqlx::Error> {
+        let previous_score = self.get(user_id).await?;
+        let (target, requested) = match update {
+            ScoreUpdate::Total(score) => (
+                score.min(previous_score.saturating_add(config.energy_max)),
+                score.saturating_sub(previous_score).max(0),
+            ),
+            ScoreUpdate::Taps(count) => (previous_score.saturating_add(count.clamp(0, config.energy_max)), count.max(0)),
+        };
+        let score = previous_score.max(target);
+        self.set_score(user_id, score);
+
+        let index = leagues::league_index(config, score);
+        let next = config.leagues.get(index + 1);
+        Ok(ScoreOutcome {
+            score,
+            requested,
+            gained: score - previous_score,
+            energy: config.energy_max,
+            unlocked_achievements: Vec::new(),
+            earned_while_away: Decimal::ZERO,
+            league: LeagueStatus {
+                league: config.leagues[index].name.clone(),
+                previous_league: None,
+                change: None,
+                changed_at: None,
+                next_league: next.map(|l| l.name.clone()),
+                next_league_score: next.map(|l| l.min_score),
+                promotion_reward: Decimal::ZERO,
+            },
+        })
+    }
+
+    async fn leaderboard(
+        &self,
+        min_score: Option<i32>,
+        max_score: Option<i32>,
+        limit: i64,
+    ) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
+        let data = self.data.lock().unwrap();
+
+        let mut entries: Vec<LeaderboardEntry> = data
+            .scores
+            .iter()
+            .filter(|(_, score)| !matches!(min_score, Some(min) if *score < min))
+            .filter(|(_, score)| !matches!(max_score, Some(max) if *score >= max))
+            .filter_map(|(user_id, score)| {
+                let user = data.users.iter().find(|u| u.id == *user_id)?;
+                Some(LeaderboardEntry {
+                    user_id: user.id,
+                    username: user.username.clone(),
+                    first_name: user.first_name.clone(),
+                    score: *score,
+                })
+            })
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        entries.truncate(usize::try_from(limit).unwrap_or(0));
+
+        Ok(entries)
+    }
+}
+
+#[async_trait]
+impl ClaimRepo for InMemoryRepo {
+    async fn create(&self, user_id: Uuid, amount: Decimal) -> Result<Claim, sqlx::Error> {
+        let claim = Claim {
+            id: Uuid::new_v4(),
+            user_id,
+            amount,
+            status: "pending".to_string(),
+            created_at: Utc::now(),
+        };
+        self.data.lock().unwrap().claims.push(claim.clone());
+        Ok(claim)
+    }
+
+    async fn find(&self, user_id: Uuid, claim_id: Uuid) -> Result<Option<Claim>, sqlx::Error> {
+        let data = self.data.lock().unwrap();
+        Ok(data.claims.iter().find(|c| c.id == claim_id && c.user_id == user_id).cloned())
+    }
+
+    async fn complete(&self, _config: &Config, claim: &Claim) -> Result<Option<Vec<UnlockedAchievement>>, sqlx::Error> {
+        let mut data = self.data.lock().unwrap();
+        let pending = data
+            .claims
+            .iter_mut()
+            .find(|c| c.id == claim.id && c.user_id == claim.user_id && c.status == "pending");
+        Ok(pending.map(|stored| {
+            stored.status = "completed".to_string();
+            Vec::new()
+        }))
+    }
+}
@@ -0,0 +1,70 @@
+//! Доступ к игрокам, очкам и заявкам через трейты: обработчики не зависят от sqlx
+//! и тестируются на хранилище в памяти.
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::achievement::UnlockedAchievement;
+use crate::models::claim::Claim;
+use crate::models::score::LeaderboardEntry;
+use crate::models::user::{TelegramUser, User};
+use crate::services::score::{ScoreOutcome, ScoreUpdate};
+
+pub mod memory;
+pub mod pg;
+
+/// Результат входа: игрок и признак того, что он создан этим запросом
+#[derive(Debug, Clone)]
+pub struct UpsertedUser {
+    pub user: User,
+    pub inserted: bool,
+}
+
+#[async_trait]
+pub trait UserRepo: Send + Sync {
+    /// Создаёт игрока (вместе с нулевым счётом) или обновляет его данные из Telegram
+    async fn upsert_telegram(&self, user: &TelegramUser) -> Result<UpsertedUser, sqlx::Error>;
+
+    /// Вход в Mini App: upsert игрока, а при первом входе — привязка к пригласившему по start_param
+    /// или по payload из /start в боте. Всё в одной транзакции: при ошибке привязки игрок не
+    /// создаётся, и повторный вход снова считается первым
+    async fn login(
+        &self,
+        config: &Config,
+        user: &TelegramUser,
+        start_param: Option<&str>,
+    ) -> Result<UpsertedUser, sqlx::Error>;
+}
+
+#[async_trait]
+pub trait ScoreRepo: Send + Sync {
+    /// Очки игрока; 0, если он ещё не играл
+    async fn get(&self, user_id: Uuid) -> Result<i32, sqlx::Error>;
+
+    /// Обновляет счёт вместе со всем, что от него зависит (см. `services::score::apply`),
+    /// в одной транзакции
+    async fn apply(&self, config: &Config, user_id: Uuid, update: ScoreUpdate) -> Result<ScoreOutcome, sqlx::Error>;
+
+    /// Лучшие игроки с очками в диапазоне [min_score, max_score)
+    async fn leaderboard(
+        &self,
+        min_score: Option<i32>,
+        max_score: Option<i32>,
+        limit: i64,
+    ) -> Result<Vec<LeaderboardEntry>, sqlx::Error>;
+}
+
+#[async_trait]
+pub trait ClaimRepo: Send + Sync {
+    /// Создаёт заявку в статусе pending
+    async fn create(&self, user_id: Uuid, amount: Decimal) -> Result<Claim, sqlx::Error>;
+
+    /// Заявка игрока; чужие заявки не возвращаются
+    async fn find(&self, user_id: Uuid, claim_id: Uuid) -> Result<Option<Claim>, sqlx::Error>;
+
+    /// Переводит заявку из pending в completed вместе с достижениями, историей статусов и уведомлением.
+    /// None — заявка уже не pending (например, её подтвердил параллельный запрос)
+    async fn complete(&self, config: &Config, claim: &Claim) -> Result<Option<Vec<UnlockedAchievement>>, sqlx::Error>;
+}
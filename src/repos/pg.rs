@@ -0,0 +1,243 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use super::{ClaimRepo, ScoreRepo, UpsertedUser, UserRepo};
+use crate::config::Config;
+use crate::models::achievement::UnlockedAchievement;
+use crate::models::claim::Claim;
+use crate::models::score::LeaderboardEntry;
+use crate::models::user::{TelegramUser, User};
+use crate::services::achievements::{self, Metric};
+use crate::services::score::{ScoreOutcome, ScoreUpdate};
+use crate::services::{claims, notifications, referral, score};
+
+/// Реализация репозиториев поверх Postgres
+#[derive(Clone)]
+pub struct PgRepo {
+    pool: PgPool,
+}
+
+impl PgRepo {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl UserRepo for PgRepo {
+    async fn upsert_telegram(&self, telegram_user: &TelegramUser) -> Result<UpsertedUser, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let upserted = upsert(&mut tx, telegram_user).await?;
+        tx.commit().await?;
+        Ok(upserted)
+    }
+
+    async fn login(
+        &self,
+        config: &Config,
+        telegram_user: &TelegramUser,
+        start_param: Option<&str>,
+    ) -> Result<UpsertedUser, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let upserted = upsert(&mut tx, telegram_user).await?;
+
+        // Реферальная привязка выполняется только при первом входе
+        if upserted.inserted {
+            let pending = sqlx::query_scalar!(
+                r#"DELETE FROM pending_referrals WHERE telegram_id = $1 RETURNING start_param"#,
+                telegram_user.id
+            )
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            if let Some(code) = referral::first_login_code(start_param, pending.as_deref()) {
+                referral::attribute(&mut tx, config, upserted.user.id, code).await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(upserted)
+    }
+}
+
+/// Создаёт игрока вместе с нулевым счётом или обновляет его данные из Telegram
+async fn upsert(conn: &mut PgConnection, telegram_user: &TelegramUser) -> Result<UpsertedUser, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        INSERT INTO users (id, telegram_id, username, first_name, last_name, allows_write_to_pm)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (telegram_id)
+        DO UPDATE SET
+            username = EXCLUDED.username,
+            first_name = EXCLUDED.first_name,
+            last_name = EXCLUDED.last_name,
+            -- Разрешение сбрасывается только когда бот получает 403 при отправке
+            allows_write_to_pm = users.allows_write_to_pm OR EXCLUDED.allows_write_to_pm
+        RETURNING id, telegram_id, username, first_name, last_name, created_at,
+            (xmax = 0) AS "inserted!"
+        "#,
+        Uuid::new_v4(),
+        telegram_user.id,
+        telegram_user.username.as_deref(),
+        telegram_user.first_name.as_deref(),
+        telegram_user.last_name.as_deref(),
+        telegram_user.allows_write_to_pm
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO scores (id, user_id, score)
+        VALUES ($1, $2, 0)
+        ON CONFLICT (user_id) DO NOTHING
+        "#,
+        Uuid::new_v4(),
+        row.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(UpsertedUser {
+        user: User {
+            id: row.id,
+            telegram_id: row.telegram_id,
+            username: row.username,
+            first_name: row.first_name,
+            last_name: row.last_name,
+            created_at: row.created_at,
+        },
+        inserted: row.inserted,
+    })
+}
+
+#[async_trait]
+impl ScoreRepo for PgRepo {
+    async fn get(&self, user_id: Uuid) -> Result<i32, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        score::get(&mut conn, user_id).await
+    }
+
+    async fn apply(&self, config: &Config, user_id: Uuid, update: ScoreUpdate) -> Result<ScoreOutcome, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let outcome = score::apply(&mut tx, config, user_id, update).await?;
+        tx.commit().await?;
+        Ok(outcome)
+    }
+
+    async fn leaderboard(
+        &self,
+        min_score: Option<i32>,
+        max_score: Option<i32>,
+        limit: i64,
+    ) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
+        sqlx::query_as!(
+            LeaderboardEntry,
+            r#"
+            SELECT
+                u.id AS user_id,
+                u.username,
+                u.first_name,
+                s.score
+            FROM scores s
+            JOIN users u ON s.user_id = u.id
+            WHERE ($1::INT IS NULL OR s.score >= $1)
+                AND ($2::INT IS NULL OR s.score < $2)
+            ORDER BY s.score DESC
+            LIMIT $3
+            "#,
+            min_score,
+            max_score,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[async_trait]
+impl ClaimRepo for PgRepo {
+    async fn create(&self, user_id: Uuid, amount: Decimal) -> Result<Claim, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let claim = sqlx::query_as!(
+            Claim,
+            r#"
+            INSERT INTO claims (id, user_id, amount, status)
+            VALUES ($1, $2, $3, 'pending')
+            RETURNING id, user_id, amount, status, created_at
+            "#,
+            Uuid::new_v4(),
+            user_id,
+            amount
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        claims::record_status(&mut tx, user_id, claim.id, &claim.status).await?;
+
+        tx.commit().await?;
+
+        Ok(claim)
+    }
+
+    async fn find(&self, user_id: Uuid, claim_id: Uuid) -> Result<Option<Claim>, sqlx::Error> {
+        sqlx::query_as!(
+            Claim,
+            r#"
+            SELECT id, user_id, amount, status, created_at
+            FROM claims
+            WHERE id = $1 AND user_id = $2
+            "#,
+            claim_id,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn complete(&self, config: &Config, claim: &Claim) -> Result<Option<Vec<UnlockedAchievement>>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        // Имитация on-chain транзакции. Условие на статус: из двух параллельных подтверждений
+        // проходит одно, второе не пишет историю и не ставит повторное уведомление
+        let amount = sqlx::query_scalar!(
+            r#"
+            UPDATE claims
+            SET status = 'completed'
+            WHERE id = $1 AND user_id = $2 AND status = 'pending'
+            RETURNING amount
+            "#,
+            claim.id,
+            claim.user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(amount) = amount else {
+            return Ok(None);
+        };
+
+        let withdrawals = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM claims WHERE user_id = $1 AND status = 'completed'"#,
+            claim.user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let unlocked = achievements::record(&mut tx, config, claim.user_id, Metric::Withdrawals, withdrawals).await?;
+
+        claims::record_status(&mut tx, claim.user_id, claim.id, "completed").await?;
+        notifications::enqueue(
+            &mut tx,
+            config,
+            claim.user_id,
+            notifications::claim_completed(claim.id, amount),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(unlocked))
+    }
+}
@@ -0,0 +1,53 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::Json,
+    routing::get,
+    Router,
+};
+
+use crate::app_state::AppState;
+use crate::models::achievement::AchievementView;
+use crate::utils::auth::extract_user_id;
+use crate::utils::errors::AppError;
+
+async fn achievements(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<AchievementView>>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let rows = sqlx::query!(
+        r#"
+        SELECT a.id, a.title, a.description, a.metric, a.threshold, a.reward,
+            ua.unlocked_at AS "unlocked_at?"
+        FROM achievements a
+        LEFT JOIN user_achievements ua ON ua.achievement_id = a.id AND ua.user_id = $1
+        WHERE a.is_active OR ua.unlocked_at IS NOT NULL
+        ORDER BY a.sort_order, a.id
+        "#,
+        user_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    
+    let views = rows
+        .into_iter()
+        .map(|row| AchievementView {
+            id: row.id,
+            title: row.title,
+            description: row.description,
+            metric: row.metric,
+            threshold: row.threshold,
+            reward: row.reward,
+            unlocked: row.unlocked_at.is_some(),
+            unlocked_at: row.unlocked_at,
+        })
+        .collect();
+    
+    Ok(Json(views))
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new().route("/", get(achievements))
+}
@@ -0,0 +1,113 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Json,
+    routing::post,
+    Router,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::models::shop::RefundResponse;
+use crate::models::tournament::{CreateTournamentRequest, Tournament};
+use crate::routes::tournament::load_tournament;
+use crate::services::payments::{self, RefundResult};
+use crate::services::{leagues, tournaments};
+use crate::utils::auth::require_admin;
+use crate::utils::errors::AppError;
+
+#[derive(Debug, Serialize)]
+pub struct CloseTournamentResponse {
+    pub success: bool,
+    pub closed: bool,
+}
+
+async fn create_tournament(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateTournamentRequest>,
+) -> Result<Json<Tournament>, AppError> {
+    require_admin(&headers, &state.config)?;
+    
+    if payload.title.trim().is_empty() {
+        return Err(AppError::Validation("Title is required".to_string()));
+    }
+    if payload.ends_at <= payload.starts_at {
+        return Err(AppError::Validation("ends_at must be after starts_at".to_string()));
+    }
+    if payload.entry_fee.is_sign_negative() || payload.prizes.iter().any(|p| p.is_sign_negative()) {
+        return Err(AppError::Validation("Entry fee and prizes must not be negative".to_string()));
+    }
+    let min_league = match payload.min_league.as_deref() {
+        Some(name) => Some(
+            leagues::find_league(&state.config, name)
+                .map(|(_, league)| league.name.clone())
+                .ok_or_else(|| AppError::Validation(format!("Unknown league {}", name)))?,
+        ),
+        None => None,
+    };
+    
+    let tournament_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        INSERT INTO tournaments (id, title, starts_at, ends_at, entry_fee, min_league, prizes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        tournament_id,
+        payload.title.trim(),
+        payload.starts_at,
+        payload.ends_at,
+        payload.entry_fee,
+        min_league,
+        &payload.prizes
+    )
+    .execute(&state.pool)
+    .await?;
+    
+    tracing::info!("Tournament created: tournament_id={}", tournament_id);
+    
+    Ok(Json(load_tournament(&state, tournament_id).await?))
+}
+
+/// Досрочно закрывает турнир и выплачивает призы
+async fn close_tournament(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(tournament_id): Path<Uuid>,
+) -> Result<Json<CloseTournamentResponse>, AppError> {
+    require_admin(&headers, &state.config)?;
+    
+    load_tournament(&state, tournament_id).await?;
+    let closed = tournaments::close(&state.pool, &state.config, tournament_id).await?;
+    
+    Ok(Json(CloseTournamentResponse {
+        success: true,
+        closed,
+    }))
+}
+
+/// Возвращает звёзды за оплаченный заказ
+async fn refund_order(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(order_id): Path<Uuid>,
+) -> Result<Json<RefundResponse>, AppError> {
+    require_admin(&headers, &state.config)?;
+    
+    match payments::refund(&state.pool, &state.bot_api, order_id).await? {
+        RefundResult::Refunded { reverted } => Ok(Json(RefundResponse {
+            success: true,
+            reverted,
+        })),
+        RefundResult::NotFound => Err(AppError::NotFound("Order not found".to_string())),
+        RefundResult::NotPaid => Err(AppError::Validation("Order is not paid".to_string())),
+    }
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new()
+        .route("/tournaments", post(create_tournament))
+        .route("/tournaments/:id/close", post(close_tournament))
+        .route("/orders/:id/refund", post(refund_order))
+}
@@ -6,10 +6,11 @@ use axum::{
 };
 use serde::Serialize;
 use std::collections::BTreeMap;
-use uuid::Uuid;
 
 use crate::app_state::AppState;
 use crate::models::user::TelegramUser;
+use crate::repos::UpsertedUser;
+use crate::services::metrics;
 use crate::utils::telegram;
 use crate::utils::jwt;
 use crate::utils::errors::AppError;
@@ -35,6 +36,13 @@ async fn authenticate_telegram(
     State(state): State<AppState>,
     Json(payload): Json<TelegramAuthRequest>,
 ) -> Result<Json<AuthResponse>, AppError> {
+    let result = sign_in(&state, payload).await;
+    metrics::record_auth(result.as_ref().map(|_| ()));
+    result.map(Json)
+}
+
+/// Проверка подписи, создание игрока и выпуск JWT
+async fn sign_in(state: &AppState, payload: TelegramAuthRequest) -> Result<AuthResponse, AppError> {
     tracing::info!("📥 Получен запрос авторизации:");
     tracing::info!("   initData присутствует: {}", payload.init_data.is_some());
     tracing::info!("   hash присутствует: {}", payload.hash.is_some());
@@ -255,68 +263,80 @@ async fn authenticate_telegram(
         last_name: user_data.get("last_name")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string()),
+        allows_write_to_pm: user_data.get("allows_write_to_pm")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
     };
     
-    // Ищем или создаём пользователя
-    let user_id = Uuid::new_v4();
-    let row = sqlx::query!(
-        r#"
-        INSERT INTO users (id, telegram_id, username, first_name, last_name)
-        VALUES ($1, $2, $3, $4, $5)
-        ON CONFLICT (telegram_id) 
-        DO UPDATE SET 
-            username = EXCLUDED.username,
-            first_name = EXCLUDED.first_name,
-            last_name = EXCLUDED.last_name
-        RETURNING id, telegram_id, username, first_name, last_name, created_at
-        "#,
-        user_id,
-        telegram_user.id,
-        telegram_user.username.as_deref(),
-        telegram_user.first_name.as_deref(),
-        telegram_user.last_name.as_deref()
-    )
-    .fetch_one(&state.pool)
-    .await?;
-    
-    let user = crate::models::user::User {
-        id: row.id,
-        telegram_id: row.telegram_id,
-        username: row.username,
-        first_name: row.first_name,
-        last_name: row.last_name,
-        created_at: row.created_at
-            .map(|dt| chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(dt, chrono::Utc))
-            .unwrap_or_else(|| chrono::Utc::now()),
-    };
+    // start_param из ссылки t.me/bot/app?startapp=... (есть только в initData)
+    let start_param = payload.init_data.as_deref()
+        .and_then(|init_data| telegram::parse_param_from_init_data(init_data, "start_param"));
     
-    // Создаём или обновляем счёт
-    sqlx::query!(
-        r#"
-        INSERT INTO scores (id, user_id, score)
-        VALUES ($1, $2, 0)
-        ON CONFLICT (user_id) DO NOTHING
-        "#,
-        Uuid::new_v4(),
-        user.id
-    )
-    .execute(&state.pool)
-    .await?;
+    // Ищем или создаём пользователя; при первом входе — реферальная привязка
+    let UpsertedUser { user, .. } = state.users.login(&state.config, &telegram_user, start_param.as_deref()).await?;
     
     // Создаём JWT токен
     let token = jwt::create_jwt(&user.id.to_string(), &state.config.jwt_secret)
         .map_err(|e| AppError::Internal(anyhow::anyhow!("JWT error: {}", e)))?;
     
+    tracing::Span::current().record("user_id", tracing::field::display(user.id));
     tracing::info!("✅ JWT токен выдан пользователю: user_id={}, telegram_id={}, username={:?}", 
         user.id, user.telegram_id, user.username);
     
-    Ok(Json(AuthResponse {
+    Ok(AuthResponse {
         token,
         user_id: user.id.to_string(),
-    }))
+    })
 }
 
 
 pub fn router() -> Router<crate::app_state::AppState> {
     Router::new().route("/telegram", post(authenticate_telegram))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    
+    use crate::repos::memory::InMemoryRepo;
+    
+    async fn login(state: AppState, telegram_id: i64) -> (StatusCode, serde_json::Value) {
+        let init_data = format!(
+            "user=%7B%22id%22%3A{}%2C%22first_name%22%3A%22Zorg%22%7D&auth_date=1&hash=mock_hash_for_development_test",
+            telegram_id
+        );
+        let request = Request::post("/telegram")
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "initData": init_data }).to_string()))
+            .unwrap();
+        let response = router().with_state(state).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+    
+    #[tokio::test]
+    async fn dev_login_creates_player_once_and_consumes_pending_referral() {
+        let repo = Arc::new(InMemoryRepo::new());
+        let mut state = AppState::for_tests(repo.clone());
+        state.config.dev_mode = true;
+        // Некорректный payload не ведёт к привязке, но забирается при первом входе
+        repo.add_pending_referral(4242, "not-a-referral");
+        
+        let (status, first) = login(state.clone(), 4242).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(first["token"].is_string());
+        assert!(!repo.has_pending_referral(4242));
+        
+        let (_, second) = login(state.clone(), 4242).await;
+        assert_eq!(first["user_id"], second["user_id"]);
+        
+        state.config.dev_mode = false;
+        let (status, _) = login(state, 4242).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,162 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+
+use crate::app_state::AppState;
+use crate::models::bot::Update;
+use crate::services::bot;
+use crate::utils::auth::require_webhook_secret;
+use crate::utils::errors::AppError;
+
+/// Принимает обновления от Telegram. Ответ отправляется прямо в ответе на webhook.
+async fn webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, AppError> {
+    require_webhook_secret(&headers, &state.config)?;
+    
+    // Telegram повторяет доставку при любом ответе кроме 2xx: нераспознанное обновление
+    // подтверждаем, чтобы оно не приходило снова
+    let update: Update = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            tracing::warn!("Skipping malformed bot update: {}", e);
+            return Ok(StatusCode::OK.into_response());
+        }
+    };
+    
+    tracing::debug!("Bot update received: update_id={}", update.update_id);
+    let reply = bot::handle_update(&state.pool, &state.config, &update).await?;
+    
+    Ok(match reply {
+        Some(reply) => Json(reply).into_response(),
+        None => StatusCode::OK.into_response(),
+    })
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new().route("/webhook", post(webhook))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    
+    use crate::repos::memory::InMemoryRepo;
+    
+    const SECRET: &str = "webhook-secret";
+    
+    /// Состояние без подключения к базе: /help и отклонённые запросы в неё не ходят
+    fn test_app() -> Router {
+        let mut state = AppState::for_tests(Arc::new(InMemoryRepo::new()));
+        state.config.telegram_webhook_secret = Some(SECRET.to_string());
+        router().with_state(state)
+    }
+    
+    async fn post_update(fixture: &str, secret: Option<&str>) -> (StatusCode, Vec<u8>) {
+        let mut request = Request::post("/webhook").header("content-type", "application/json");
+        if let Some(secret) = secret {
+            request = request.header("x-telegram-bot-api-secret-token", secret);
+        }
+        let response = test_app()
+            .oneshot(request.body(Body::from(fixture.to_string())).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, body.to_vec())
+    }
+    
+    const HELP: &str = include_str!("../../tests/fixtures/telegram/help.json");
+    const START_REFERRAL: &str = include_str!("../../tests/fixtures/telegram/start_referral.json");
+    const STATS: &str = include_str!("../../tests/fixtures/telegram/stats.json");
+    const GROUP_LEADERBOARD: &str = include_str!("../../tests/fixtures/telegram/group_leaderboard.json");
+    const EDITED_MESSAGE: &str = include_str!("../../tests/fixtures/telegram/edited_message.json");
+    const PRE_CHECKOUT_QUERY: &str = include_str!("../../tests/fixtures/telegram/pre_checkout_query.json");
+    const SUCCESSFUL_PAYMENT: &str = include_str!("../../tests/fixtures/telegram/successful_payment.json");
+    
+    #[test]
+    fn fixtures_deserialize_into_updates() {
+        let start: Update = serde_json::from_str(START_REFERRAL).unwrap();
+        let message = start.message.unwrap();
+        assert_eq!(message.from.unwrap().id, 515151);
+        assert_eq!(message.chat.kind, "private");
+        assert_eq!(
+            bot::parse_command(message.text.as_deref().unwrap()),
+            Some(bot::Command::Start(Some("ref_ABC123")))
+        );
+        
+        let stats: Update = serde_json::from_str(STATS).unwrap();
+        assert_eq!(bot::parse_command(stats.message.unwrap().text.as_deref().unwrap()), Some(bot::Command::Stats));
+        
+        let group: Update = serde_json::from_str(GROUP_LEADERBOARD).unwrap();
+        let message = group.message.unwrap();
+        assert_eq!(message.chat.kind, "supergroup");
+        assert_eq!(bot::parse_command(message.text.as_deref().unwrap()), Some(bot::Command::Leaderboard));
+        
+        let edited: Update = serde_json::from_str(EDITED_MESSAGE).unwrap();
+        assert!(edited.message.is_none());
+        
+        let query: Update = serde_json::from_str(PRE_CHECKOUT_QUERY).unwrap();
+        let query = query.pre_checkout_query.unwrap();
+        assert_eq!(query.from.id, 424242);
+        assert_eq!(query.currency, "XTR");
+        assert_eq!(query.total_amount, 50);
+        
+        let paid: Update = serde_json::from_str(SUCCESSFUL_PAYMENT).unwrap();
+        let payment = paid.message.unwrap().successful_payment.unwrap();
+        assert_eq!(payment.invoice_payload, "6f1c2a9e-3b7d-4c1e-9a55-0d2f8e4b7c61");
+        assert_eq!(payment.telegram_payment_charge_id, "stxAbCdEf123456");
+    }
+    
+    #[tokio::test]
+    async fn rejects_missing_or_wrong_secret() {
+        assert_eq!(post_update(HELP, None).await.0, StatusCode::UNAUTHORIZED);
+        assert_eq!(post_update(HELP, Some("wrong")).await.0, StatusCode::UNAUTHORIZED);
+    }
+    
+    #[tokio::test]
+    async fn replies_to_help_with_send_message() {
+        let (status, body) = post_update(HELP, Some(SECRET)).await;
+        assert_eq!(status, StatusCode::OK);
+        
+        let reply: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(reply["method"], "sendMessage");
+        assert_eq!(reply["chat_id"], 424242);
+        assert_eq!(reply["parse_mode"], "HTML");
+        assert!(reply["text"].as_str().unwrap().contains("/leaderboard"));
+    }
+    
+    #[tokio::test]
+    async fn acknowledges_updates_without_commands() {
+        let (status, body) = post_update(EDITED_MESSAGE, Some(SECRET)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.is_empty());
+        
+        let (status, _) = post_update("not json", Some(SECRET)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    
+    #[tokio::test]
+    async fn rejects_pre_checkout_for_unknown_order() {
+        // payload не является id заказа, поэтому база не нужна
+        let (status, body) = post_update(PRE_CHECKOUT_QUERY, Some(SECRET)).await;
+        assert_eq!(status, StatusCode::OK);
+        
+        let reply: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(reply["method"], "answerPreCheckoutQuery");
+        assert_eq!(reply["pre_checkout_query_id"], "4382bfdwdsb323b2d9");
+        assert_eq!(reply["ok"], false);
+        assert!(reply["error_message"].is_string());
+    }
+}
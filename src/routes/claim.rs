@@ -1,17 +1,32 @@
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
 use axum::{
-    extract::State,
-    http::HeaderMap,
-    response::Json,
-    routing::post,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+    routing::{get, post},
     Router,
 };
+use futures_util::stream;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 use serde::Serialize;
 use uuid::Uuid;
 
 use crate::app_state::AppState;
-use crate::models::claim::{CreateClaimRequest, ConfirmClaimRequest};
+use crate::models::achievement::UnlockedAchievement;
+use crate::models::claim::{ClaimEventsQuery, ClaimStatusUpdate, CreateClaimRequest, ConfirmClaimRequest};
+use crate::models::event::AppEvent;
+use crate::services::metrics;
+use crate::services::events::BusMessage;
 use crate::utils::errors::AppError;
-use crate::utils::jwt;
+use crate::utils::auth::{extract_user_id, extract_user_id_or_token};
 
 #[derive(Debug, Serialize)]
 pub struct CreateClaimResponse {
@@ -23,27 +38,7 @@ pub struct CreateClaimResponse {
 pub struct ConfirmClaimResponse {
     pub success: bool,
     pub status: String,
-}
-
-// Извлекаем JWT токен из заголовка Authorization
-async fn extract_user_id(
-    headers: &HeaderMap,
-    jwt_secret: &str,
-) -> Result<Uuid, AppError> {
-    let auth_header = headers
-        .get("authorization")
-        .and_then(|h| h.to_str().ok())
-        .ok_or_else(|| AppError::Unauthorized)?;
-    
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or_else(|| AppError::Unauthorized)?;
-    
-    let claims = jwt::verify_jwt(token, jwt_secret)
-        .map_err(|_| AppError::Unauthorized)?;
-    
-    Uuid::parse_str(&claims.sub)
-        .map_err(|_| AppError::Validation("Invalid user ID in token".to_string()))
+    pub unlocked_achievements: Vec<UnlockedAchievement>,
 }
 
 async fn create_claim(
@@ -51,25 +46,18 @@ async fn create_claim(
     headers: HeaderMap,
     Json(payload): Json<CreateClaimRequest>,
 ) -> Result<Json<CreateClaimResponse>, AppError> {
-    let user_id = extract_user_id(&headers, &state.config.jwt_secret).await?;
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
     
-    let claim_id = Uuid::new_v4();
+    if payload.amount <= Decimal::ZERO {
+        return Err(AppError::Validation("Amount must be positive".to_string()));
+    }
     
-    sqlx::query(
-        r#"
-        INSERT INTO claims (id, user_id, amount, status)
-        VALUES ($1, $2, $3::DECIMAL, 'pending')
-        "#,
-    )
-    .bind(claim_id)
-    .bind(user_id)
-    .bind(payload.amount.to_string())
-    .execute(&state.pool)
-    .await?;
+    let claim = state.claims.create(user_id, payload.amount).await?;
+    metrics::record_claim("pending");
     
     Ok(Json(CreateClaimResponse {
-        claim_id: claim_id.to_string(),
-        status: "pending".to_string(),
+        claim_id: claim.id.to_string(),
+        status: claim.status,
     }))
 }
 
@@ -78,46 +66,224 @@ async fn confirm_claim(
     headers: HeaderMap,
     Json(payload): Json<ConfirmClaimRequest>,
 ) -> Result<Json<ConfirmClaimResponse>, AppError> {
-    let user_id = extract_user_id(&headers, &state.config.jwt_secret).await?;
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
     
     // Проверяем, что claim принадлежит пользователю
-    let claim = sqlx::query!(
+    let claim = state
+        .claims
+        .find(user_id, payload.claim_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Claim not found".to_string()))?;
+    
+    // Статус проверяется в самом UPDATE: прочитанный выше мог устареть
+    let unlocked_achievements = state
+        .claims
+        .complete(&state.config, &claim)
+        .await?
+        .ok_or_else(|| AppError::Validation("Claim is not in pending status".to_string()))?;
+    metrics::record_claim("completed");
+    
+    Ok(Json(ConfirmClaimResponse {
+        success: true,
+        status: "completed".to_string(),
+        unlocked_achievements,
+    }))
+}
+
+/// Состояние SSE-потока: события из базы и из шины отдаются по возрастанию номера без повторов
+struct StatusStream {
+    pool: PgPool,
+    claim_id: Uuid,
+    last_event_id: i64,
+    pending: VecDeque<(i64, String)>,
+    bus: broadcast::Receiver<BusMessage>,
+    /// При остановке сервера поток завершается, клиент переподключается с Last-Event-ID
+    shutdown: CancellationToken,
+    /// Финальный статус отдан: дальше переходов не будет
+    finished: bool,
+}
+
+/// После этого статуса заявка больше не меняется
+fn is_terminal(status: &str) -> bool {
+    status == "completed"
+}
+
+async fn load_status_events(pool: &PgPool, claim_id: Uuid, after: i64) -> Result<Vec<(i64, String)>, sqlx::Error> {
+    let rows = sqlx::query!(
         r#"
-        SELECT id, status FROM claims
-        WHERE id = $1 AND user_id = $2
+        SELECT id, status FROM claim_status_events
+        WHERE claim_id = $1 AND id > $2
+        ORDER BY id
         "#,
-        payload.claim_id,
-        user_id
+        claim_id,
+        after
     )
-    .fetch_optional(&state.pool)
+    .fetch_all(pool)
     .await?;
     
-    let claim = claim.ok_or_else(|| AppError::NotFound("Claim not found".to_string()))?;
+    Ok(rows.into_iter().map(|row| (row.id, row.status)).collect())
+}
+
+impl StatusStream {
+    async fn next_event(&mut self) -> Option<Event> {
+        if self.finished {
+            return None;
+        }
+        loop {
+            if let Some((id, status)) = self.pending.pop_front() {
+                if id <= self.last_event_id {
+                    continue;
+                }
+                self.last_event_id = id;
+                self.finished = is_terminal(&status);
+                let update = ClaimStatusUpdate {
+                    claim_id: self.claim_id,
+                    status,
+                };
+                return Event::default().id(id.to_string()).event("status").json_data(update).ok();
+            }
+            
+            let message = tokio::select! {
+                _ = self.shutdown.cancelled() => return None,
+                message = self.bus.recv() => message,
+            };
+            let message = match message {
+                Ok(message) => message,
+                Err(broadcast::error::RecvError::Lagged(_)) => BusMessage::Resync,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            };
+            match message {
+                BusMessage::Event(AppEvent::ClaimStatusChanged { claim_id, event_id, status, .. })
+                    if claim_id == self.claim_id =>
+                {
+                    self.pending.push_back((event_id, status));
+                }
+                BusMessage::Event(_) => {}
+                // События могли потеряться — дочитываем из базы
+                BusMessage::Resync => match load_status_events(&self.pool, self.claim_id, self.last_event_id).await {
+                    Ok(events) => self.pending.extend(events),
+                    Err(e) => {
+                        tracing::error!("Failed to reload claim events: claim_id={}, error={}", self.claim_id, e);
+                        return None;
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// SSE-поток переходов статуса заявки. Поддерживает Last-Event-ID и токен в query.
+/// Поток закрывается после финального статуса.
+async fn claim_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(claim_id): Path<Uuid>,
+    Query(query): Query<ClaimEventsQuery>,
+) -> Result<Response, AppError> {
+    let user_id = extract_user_id_or_token(&headers, query.token.as_deref(), &state.config.jwt_secret)?;
+    
+    let claim = state.claims.find(user_id, claim_id).await?
+        .ok_or_else(|| AppError::NotFound("Claim not found".to_string()))?;
+    
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|id| id.trim().parse().ok())
+        .unwrap_or(0);
+    
+    // Подписываемся до чтения истории, чтобы не пропустить переход между ними
+    let bus = state.events.subscribe();
+    let pending: VecDeque<_> = load_status_events(&state.pool, claim_id, last_event_id).await?.into();
     
-    if claim.status.as_deref() != Some("pending") {
-        return Err(AppError::Validation("Claim is not in pending status".to_string()));
+    // Клиент уже получил финальный статус и переподключился: 204 останавливает EventSource
+    if is_terminal(&claim.status) && pending.is_empty() {
+        return Ok(StatusCode::NO_CONTENT.into_response());
     }
     
-    // Обновляем статус на completed (имитация on-chain транзакции)
-    sqlx::query!(
-        r#"
-        UPDATE claims
-        SET status = 'completed'
-        WHERE id = $1
-        "#,
-        payload.claim_id
-    )
-    .execute(&state.pool)
-    .await?;
+    let stream = StatusStream {
+        pool: state.pool.clone(),
+        claim_id,
+        last_event_id,
+        pending,
+        bus,
+        shutdown: state.shutdown.clone(),
+        finished: false,
+    };
+    let stream = stream::unfold(stream, |mut stream| async move {
+        stream.next_event().await.map(|event| (Ok::<_, Infallible>(event), stream))
+    });
     
-    Ok(Json(ConfirmClaimResponse {
-        success: true,
-        status: "completed".to_string(),
-    }))
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()).into_response())
 }
 
 pub fn router() -> Router<crate::app_state::AppState> {
     Router::new()
         .route("/start", post(create_claim))
         .route("/confirm", post(confirm_claim))
+        .route("/:id/events", get(claim_events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    
+    use crate::repos::memory::InMemoryRepo;
+    use crate::utils::jwt;
+    
+    async fn post(state: AppState, uri: &str, user_id: Uuid, body: serde_json::Value) -> StatusCode {
+        let token = jwt::create_jwt(&user_id.to_string(), &state.config.jwt_secret).unwrap();
+        let request = Request::post(uri)
+            .header("authorization", format!("Bearer {}", token))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        router().with_state(state).oneshot(request).await.unwrap().status()
+    }
+    
+    #[tokio::test]
+    async fn start_validates_amount_and_creates_pending_claim() {
+        let repo = Arc::new(InMemoryRepo::new());
+        let state = AppState::for_tests(repo.clone());
+        let user_id = Uuid::new_v4();
+        
+        let status = post(state.clone(), "/start", user_id, serde_json::json!({ "amount": "0" })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(repo.claims().is_empty());
+        
+        let status = post(state, "/start", user_id, serde_json::json!({ "amount": "12.5" })).await;
+        assert_eq!(status, StatusCode::OK);
+        let claims = repo.claims();
+        assert_eq!(claims.len(), 1);
+        assert_eq!(claims[0].user_id, user_id);
+        assert_eq!(claims[0].status, "pending");
+    }
+    
+    #[tokio::test]
+    async fn confirm_rejects_claims_of_other_players() {
+        let repo = Arc::new(InMemoryRepo::new());
+        let state = AppState::for_tests(repo.clone());
+        let claim = state.claims.create(Uuid::new_v4(), Decimal::ONE).await.unwrap();
+        
+        let status = post(state, "/confirm", Uuid::new_v4(), serde_json::json!({ "claim_id": claim.id })).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn confirm_completes_pending_claim_once() {
+        let repo = Arc::new(InMemoryRepo::new());
+        let state = AppState::for_tests(repo.clone());
+        let user_id = Uuid::new_v4();
+        let claim = state.claims.create(user_id, Decimal::ONE).await.unwrap();
+        
+        let status = post(state.clone(), "/confirm", user_id, serde_json::json!({ "claim_id": claim.id })).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(repo.claims()[0].status, "completed");
+        
+        let status = post(state, "/confirm", user_id, serde_json::json!({ "claim_id": claim.id })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
 }
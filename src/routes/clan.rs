@@ -0,0 +1,288 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::models::clan::{ClanMember, ClanSummary, CreateClanRequest, MembershipRecord};
+use crate::services::clans::{self, JoinResult};
+use crate::utils::auth::extract_user_id;
+use crate::utils::errors::AppError;
+
+#[derive(Debug, Serialize)]
+pub struct ClanResponse {
+    #[serde(flatten)]
+    pub clan: ClanSummary,
+    pub owner_id: Option<Uuid>,
+    pub max_members: i64,
+    pub members: Vec<ClanMember>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MembershipResponse {
+    pub success: bool,
+    pub clan_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryResponse {
+    pub season: String,
+    pub current_clan_id: Option<Uuid>,
+    pub history: Vec<MembershipRecord>,
+}
+
+fn join_error(result: JoinResult) -> AppError {
+    match result {
+        JoinResult::ClanNotFound => AppError::NotFound("Clan not found".to_string()),
+        JoinResult::AlreadyInClan => AppError::Validation("Leave your current clan first".to_string()),
+        JoinResult::SeasonLocked => {
+            AppError::Validation("You already played for another clan this season".to_string())
+        }
+        JoinResult::ClanFull => AppError::Validation("Clan is full".to_string()),
+        JoinResult::Joined => AppError::Internal(anyhow::anyhow!("Unexpected join result")),
+    }
+}
+
+async fn create_clan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateClanRequest>,
+) -> Result<Json<MembershipResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+
+    let name = payload.name.trim();
+    if !(3..=32).contains(&name.chars().count()) {
+        return Err(AppError::Validation("Clan name must be 3 to 32 characters".to_string()));
+    }
+
+    let clan_id = Uuid::new_v4();
+    let mut tx = state.pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO clans (id, name, telegram_channel, owner_id)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        clan_id,
+        name,
+        payload.telegram_channel.as_deref(),
+        user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::Validation("Clan name is already taken".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    match clans::join(&mut tx, &state.config, user_id, clan_id).await? {
+        JoinResult::Joined => {}
+        result => return Err(join_error(result)),
+    }
+
+    tx.commit().await?;
+
+    tracing::info!("Clan created: clan_id={}, owner_id={}", clan_id, user_id);
+
+    Ok(Json(MembershipResponse {
+        success: true,
+        clan_id: Some(clan_id),
+    }))
+}
+
+async fn join_clan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(clan_id): Path<Uuid>,
+) -> Result<Json<MembershipResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+
+    let mut tx = state.pool.begin().await?;
+    match clans::join(&mut tx, &state.config, user_id, clan_id).await? {
+        JoinResult::Joined => {}
+        result => return Err(join_error(result)),
+    }
+    tx.commit().await?;
+
+    Ok(Json(MembershipResponse {
+        success: true,
+        clan_id: Some(clan_id),
+    }))
+}
+
+async fn leave_clan(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<MembershipResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+
+    let mut tx = state.pool.begin().await?;
+    if !clans::leave(&mut tx, user_id).await? {
+        return Err(AppError::Validation("You are not in a clan".to_string()));
+    }
+    tx.commit().await?;
+
+    Ok(Json(MembershipResponse {
+        success: true,
+        clan_id: None,
+    }))
+}
+
+async fn get_clan(
+    State(state): State<AppState>,
+    Path(clan_id): Path<Uuid>,
+) -> Result<Json<ClanResponse>, AppError> {
+    let season = clans::current_season(&state.config);
+    let clan = sqlx::query!(
+        r#"
+        SELECT c.id, c.name, c.telegram_channel, c.owner_id,
+            (SELECT COUNT(*) FROM clan_members m WHERE m.clan_id = c.id) AS "member_count!",
+            (SELECT COALESCE(SUM(h.score), 0) FROM clan_membership_history h
+                WHERE h.clan_id = c.id AND h.season = $2)::BIGINT AS "score!"
+        FROM clans c
+        WHERE c.id = $1
+        "#,
+        clan_id,
+        season
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Clan not found".to_string()))?;
+
+    let members = sqlx::query!(
+        r#"
+        SELECT u.id AS user_id, u.username, u.first_name,
+            COALESCE(s.score, 0) AS "score!",
+            (SELECT COALESCE(SUM(h.score), 0) FROM clan_membership_history h
+                WHERE h.user_id = m.user_id AND h.clan_id = m.clan_id AND h.season = $2)::BIGINT AS "season_score!",
+            m.joined_at
+        FROM clan_members m
+        JOIN users u ON u.id = m.user_id
+        LEFT JOIN scores s ON s.user_id = m.user_id
+        WHERE m.clan_id = $1
+        ORDER BY 5 DESC, m.joined_at
+        "#,
+        clan_id,
+        season
+    )
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|row| ClanMember {
+        user_id: row.user_id,
+        username: row.username,
+        first_name: row.first_name,
+        score: row.score,
+        season_score: row.season_score,
+        joined_at: row.joined_at,
+    })
+    .collect();
+
+    Ok(Json(ClanResponse {
+        clan: ClanSummary {
+            id: clan.id,
+            name: clan.name,
+            telegram_channel: clan.telegram_channel,
+            member_count: clan.member_count,
+            score: clan.score,
+        },
+        owner_id: clan.owner_id,
+        max_members: state.config.clan_max_members,
+        members,
+    }))
+}
+
+/// Топ кланов по очкам, набранным за клан в текущем сезоне
+async fn clan_leaderboard(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ClanSummary>>, AppError> {
+    let clans = sqlx::query!(
+        r#"
+        SELECT c.id, c.name, c.telegram_channel,
+            (SELECT COUNT(*) FROM clan_members m WHERE m.clan_id = c.id) AS "member_count!",
+            h.score AS "score!"
+        FROM (
+            SELECT clan_id, SUM(score)::BIGINT AS score
+            FROM clan_membership_history
+            WHERE season = $1
+            GROUP BY clan_id
+        ) h
+        JOIN clans c ON c.id = h.clan_id
+        ORDER BY h.score DESC, c.created_at
+        LIMIT 10
+        "#,
+        clans::current_season(&state.config)
+    )
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|row| ClanSummary {
+        id: row.id,
+        name: row.name,
+        telegram_channel: row.telegram_channel,
+        member_count: row.member_count,
+        score: row.score,
+    })
+    .collect();
+
+    Ok(Json(clans))
+}
+
+async fn membership_history(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<HistoryResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+
+    let current_clan_id = sqlx::query_scalar!(
+        r#"SELECT clan_id FROM clan_members WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?;
+
+    let history = sqlx::query!(
+        r#"
+        SELECT h.clan_id, c.name AS clan_name, h.season, h.joined_at, h.left_at
+        FROM clan_membership_history h
+        JOIN clans c ON c.id = h.clan_id
+        WHERE h.user_id = $1
+        ORDER BY h.joined_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|row| MembershipRecord {
+        clan_id: row.clan_id,
+        clan_name: row.clan_name,
+        season: row.season,
+        joined_at: row.joined_at,
+        left_at: row.left_at,
+    })
+    .collect();
+
+    Ok(Json(HistoryResponse {
+        season: clans::current_season(&state.config),
+        current_clan_id,
+        history,
+    }))
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new()
+        .route("/", post(create_clan))
+        .route("/leaderboard", get(clan_leaderboard))
+        .route("/history", get(membership_history))
+        .route("/leave", post(leave_clan))
+        .route("/:id", get(get_clan))
+        .route("/:id/join", post(join_clan))
+}
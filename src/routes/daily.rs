@@ -0,0 +1,80 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::app_state::AppState;
+use crate::models::achievement::UnlockedAchievement;
+use crate::services::daily;
+use crate::utils::auth::extract_user_id;
+use crate::utils::errors::AppError;
+
+#[derive(Debug, Serialize)]
+pub struct DailyStatusResponse {
+    pub day: NaiveDate,
+    pub streak: i32,
+    pub checked_in_today: bool,
+    /// Награда за следующую отметку (сегодня или завтра, если сегодня уже отмечался)
+    pub next_reward: Decimal,
+    pub rewards: Vec<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckInResponse {
+    pub success: bool,
+    pub day: NaiveDate,
+    pub streak: i32,
+    pub reward: Decimal,
+    pub unlocked_achievements: Vec<UnlockedAchievement>,
+}
+
+async fn daily_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<DailyStatusResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let status = daily::status(&state.pool, &state.config, user_id).await?;
+    let next_reward = daily::reward_for_streak(&state.config, status.streak + 1);
+    
+    Ok(Json(DailyStatusResponse {
+        day: status.day,
+        streak: status.streak,
+        checked_in_today: status.checked_in_today,
+        next_reward,
+        rewards: state.config.daily_rewards.clone(),
+    }))
+}
+
+async fn check_in(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<CheckInResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let check_in = daily::check_in(&state.pool, &state.config, user_id)
+        .await?
+        .ok_or_else(|| AppError::Validation("Already checked in today".to_string()))?;
+    
+    tracing::info!("Daily check-in: user_id={}, streak={}, reward={}", user_id, check_in.streak, check_in.reward);
+    
+    Ok(Json(CheckInResponse {
+        success: true,
+        day: check_in.day,
+        streak: check_in.streak,
+        reward: check_in.reward,
+        unlocked_achievements: check_in.unlocked_achievements,
+    }))
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new()
+        .route("/", get(daily_status))
+        .route("/checkin", post(check_in))
+}
@@ -1,43 +1,70 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
     http::HeaderMap,
     response::Json,
     routing::{get, post},
     Router,
 };
-use serde::Serialize;
-use uuid::Uuid;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 use crate::app_state::AppState;
+use crate::config::Config;
+use crate::models::achievement::UnlockedAchievement;
+use crate::models::league::LeagueStatus;
 use crate::models::score::{UpdateScoreRequest, LeaderboardEntry};
+use crate::services::balance;
+use crate::services::leagues;
+use crate::services::passive::{self, UpgradeResult};
+use crate::services::metrics;
+use crate::services::score::{self, ScoreUpdate};
 use crate::utils::errors::AppError;
-use crate::utils::jwt;
+use crate::utils::auth::extract_user_id;
 
 #[derive(Debug, Serialize)]
 pub struct UpdateScoreResponse {
     pub success: bool,
     pub score: i32,
+    pub unlocked_achievements: Vec<UnlockedAchievement>,
+    /// Пассивный доход, начисленный с прошлого запроса
+    pub earned_while_away: Decimal,
+    pub league: LeagueStatus,
+    /// Энергия после списания за прирост счёта
+    pub energy: i32,
 }
 
-// Извлекаем JWT токен из заголовка Authorization
-async fn extract_user_id(
-    headers: &HeaderMap,
-    jwt_secret: &str,
-) -> Result<Uuid, AppError> {
-    let auth_header = headers
-        .get("authorization")
-        .and_then(|h| h.to_str().ok())
-        .ok_or_else(|| AppError::Unauthorized)?;
-    
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or_else(|| AppError::Unauthorized)?;
-    
-    let claims = jwt::verify_jwt(token, jwt_secret)
-        .map_err(|_| AppError::Unauthorized)?;
-    
-    Uuid::parse_str(&claims.sub)
-        .map_err(|_| AppError::Validation("Invalid user ID in token".to_string()))
+#[derive(Debug, Serialize)]
+pub struct PassiveIncomeInfo {
+    pub level: i32,
+    pub income_per_hour: Decimal,
+    pub next_upgrade_cost: Option<Decimal>,
+    pub max_offline_hours: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameStateResponse {
+    pub score: i32,
+    pub balance: Decimal,
+    pub passive_income: PassiveIncomeInfo,
+    pub earned_while_away: Decimal,
+    pub league: LeagueStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpgradePassiveResponse {
+    pub success: bool,
+    pub level: i32,
+    pub cost: Decimal,
+    pub passive_income: PassiveIncomeInfo,
+}
+
+fn passive_income_info(config: &Config, level: i32) -> PassiveIncomeInfo {
+    PassiveIncomeInfo {
+        level,
+        income_per_hour: passive::income_per_hour(config, level),
+        next_upgrade_cost: passive::upgrade_cost(config, level),
+        max_offline_hours: config.passive_income_max_hours,
+    }
 }
 
 async fn update_score(
@@ -45,66 +72,218 @@ async fn update_score(
     headers: HeaderMap,
     Json(payload): Json<UpdateScoreRequest>,
 ) -> Result<Json<UpdateScoreResponse>, AppError> {
-    let user_id = extract_user_id(&headers, &state.config.jwt_secret).await?;
-    
-    // Обновляем счёт пользователя
-    let score = sqlx::query!(
-        r#"
-        INSERT INTO scores (id, user_id, score)
-        VALUES ($1, $2, $3)
-        ON CONFLICT (user_id) 
-        DO UPDATE SET 
-            score = GREATEST(scores.score, $3),
-            updated_at = now()
-        RETURNING score
-        "#,
-        Uuid::new_v4(),
-        user_id,
-        payload.score
-    )
-    .fetch_one(&state.pool)
-    .await?;
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    if payload.score < 0 {
+        return Err(AppError::Validation("Score must not be negative".to_string()));
+    }
+    
+    let outcome = state.scores.apply(&state.config, user_id, ScoreUpdate::Total(payload.score)).await?;
+    metrics::record_taps("http", outcome.requested, outcome.gained);
     
     Ok(Json(UpdateScoreResponse {
         success: true,
-        score: score.score.unwrap_or(0),
+        score: outcome.score,
+        unlocked_achievements: outcome.unlocked_achievements,
+        earned_while_away: outcome.earned_while_away,
+        league: outcome.league,
+        energy: outcome.energy,
     }))
 }
 
+/// Текущее состояние игры; заодно начисляет пассивный доход за время отсутствия
+async fn game_state(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<GameStateResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let mut tx = state.pool.begin().await?;
+    
+    let accrual = passive::accrue(&mut tx, &state.config, user_id).await?;
+    let score = score::get(&mut tx, user_id).await?;
+    let league = leagues::sync(&mut tx, &state.config, user_id, score).await?;
+    let balance = balance::get(&mut tx, user_id).await?;
+    
+    tx.commit().await?;
+    
+    Ok(Json(GameStateResponse {
+        score,
+        balance,
+        passive_income: passive_income_info(&state.config, accrual.level),
+        earned_while_away: accrual.earned,
+        league,
+    }))
+}
+
+async fn upgrade_passive(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<UpgradePassiveResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let mut tx = state.pool.begin().await?;
+    
+    let (level, cost) = match passive::upgrade(&mut tx, &state.config, user_id).await? {
+        UpgradeResult::Upgraded { level, cost } => (level, cost),
+        UpgradeResult::MaxLevel => {
+            return Err(AppError::Validation("Passive income is already at max level".to_string()))
+        }
+        UpgradeResult::InsufficientBalance => {
+            return Err(AppError::Validation("Insufficient balance".to_string()))
+        }
+    };
+    
+    tx.commit().await?;
+    
+    tracing::info!("Passive income upgraded: user_id={}, level={}", user_id, level);
+    
+    Ok(Json(UpgradePassiveResponse {
+        success: true,
+        level,
+        cost,
+        passive_income: passive_income_info(&state.config, level),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    /// Название лиги: топ только среди игроков этой лиги
+    pub league: Option<String>,
+}
+
 async fn leaderboard(
     State(state): State<AppState>,
+    Query(query): Query<LeaderboardQuery>,
 ) -> Result<Json<Vec<LeaderboardEntry>>, AppError> {
-    let rows = sqlx::query!(
-        r#"
-        SELECT 
-            u.id as user_id,
-            u.username,
-            u.first_name,
-            s.score
-        FROM scores s
-        JOIN users u ON s.user_id = u.id
-        ORDER BY s.score DESC
-        LIMIT 10
-        "#
-    )
-    .fetch_all(&state.pool)
-    .await?;
-    
-    let entries: Vec<LeaderboardEntry> = rows
-        .into_iter()
-        .map(|row| LeaderboardEntry {
-            user_id: row.user_id,
-            username: row.username,
-            first_name: row.first_name,
-            score: row.score.unwrap_or(0),
-        })
-        .collect();
+    let (min_score, max_score) = match query.league.as_deref() {
+        Some(name) => {
+            let (index, _) = leagues::find_league(&state.config, name)
+                .ok_or_else(|| AppError::NotFound(format!("League {} not found", name)))?;
+            let (min, max) = leagues::score_range(&state.config, index);
+            (Some(min), max)
+        }
+        None => (None, None),
+    };
+    
+    let entries = state.scores.leaderboard(min_score, max_score, 10).await?;
     
     Ok(Json(entries))
 }
 
+/// Лига игрока; при изменении порогов или очков лига пересчитывается
+async fn league(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<LeagueStatus>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let mut tx = state.pool.begin().await?;
+    let score = score::get(&mut tx, user_id).await?;
+    let status = leagues::sync(&mut tx, &state.config, user_id, score).await?;
+    
+    tx.commit().await?;
+    
+    Ok(Json(status))
+}
+
 pub fn router() -> Router<crate::app_state::AppState> {
     Router::new()
+        .route("/state", get(game_state))
         .route("/update_score", post(update_score))
+        .route("/passive/upgrade", post(upgrade_passive))
         .route("/leaderboard", get(leaderboard))
+        .route("/league", get(league))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use std::sync::Arc;
+    use tower::ServiceExt;
+    
+    use crate::models::user::TelegramUser;
+    use crate::repos::memory::InMemoryRepo;
+    use crate::repos::{ScoreRepo, UserRepo};
+    use crate::utils::jwt;
+    
+    async fn player(repo: &InMemoryRepo, telegram_id: i64, score: i32) {
+        let telegram_user = TelegramUser {
+            id: telegram_id,
+            username: Some(format!("player{}", telegram_id)),
+            first_name: None,
+            last_name: None,
+            allows_write_to_pm: false,
+        };
+        let upserted = repo.upsert_telegram(&telegram_user).await.unwrap();
+        repo.set_score(upserted.user.id, score);
+    }
+    
+    async fn get_leaderboard(state: AppState, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = router()
+            .with_state(state)
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+    
+    #[tokio::test]
+    async fn leaderboard_is_sorted_and_filtered_by_league() {
+        let repo = Arc::new(InMemoryRepo::new());
+        let state = AppState::for_tests(repo.clone());
+        let (min, max) = leagues::score_range(&state.config, 1);
+        player(&repo, 1, min - 1).await;
+        player(&repo, 2, min).await;
+        player(&repo, 3, max.unwrap_or(min) + 5).await;
+        
+        let (status, all) = get_leaderboard(state.clone(), "/leaderboard").await;
+        assert_eq!(status, StatusCode::OK);
+        let usernames: Vec<&str> = all.as_array().unwrap().iter().map(|e| e["username"].as_str().unwrap()).collect();
+        assert_eq!(usernames, ["player3", "player2", "player1"]);
+        
+        let league = state.config.leagues[1].name.clone();
+        let (_, filtered) = get_leaderboard(state.clone(), &format!("/leaderboard?league={}", league)).await;
+        let filtered = filtered.as_array().unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0]["username"], "player2");
+        
+        let (status, _) = get_leaderboard(state, "/leaderboard?league=Nowhere").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+    
+    async fn post_score(state: AppState, user_id: uuid::Uuid, score: i32) -> (StatusCode, serde_json::Value) {
+        let token = jwt::create_jwt(&user_id.to_string(), &state.config.jwt_secret).unwrap();
+        let request = Request::post("/update_score")
+            .header("authorization", format!("Bearer {}", token))
+            .header("content-type", "application/json")
+            .body(Body::from(serde_json::json!({ "score": score }).to_string()))
+            .unwrap();
+        let response = router().with_state(state).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+    
+    #[tokio::test]
+    async fn update_score_goes_through_the_score_repo_and_only_grows() {
+        let repo = Arc::new(InMemoryRepo::new());
+        let state = AppState::for_tests(repo.clone());
+        let user_id = uuid::Uuid::new_v4();
+        
+        let (status, body) = post_score(state.clone(), user_id, 120).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["score"], 120);
+        
+        let (_, body) = post_score(state.clone(), user_id, 80).await;
+        assert_eq!(body["score"], 120);
+        assert_eq!(repo.get(user_id).await.unwrap(), 120);
+        
+        let (status, _) = post_score(state, user_id, -1).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
 }
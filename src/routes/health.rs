@@ -0,0 +1,107 @@
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde_json::{json, Map, Value};
+
+use crate::app_state::AppState;
+use crate::db;
+
+/// Совместимость со старыми проверками: процесс отвечает
+async fn health() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Liveness: процесс жив и обрабатывает запросы; зависимости не проверяются,
+/// чтобы оркестратор не перезапускал инстанс из-за недоступной базы
+async fn live() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Readiness: база отвечает, схема актуальна, фоновые задачи живы, инстанс не останавливается
+async fn ready(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    let timeout = Duration::from_millis(state.config.health_db_timeout_ms);
+    let mut checks = Map::new();
+    let mut ready = true;
+
+    let started = Instant::now();
+    let database = tokio::time::timeout(timeout, sqlx::query("SELECT 1").execute(&state.pool)).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let database_ok = matches!(database, Ok(Ok(_)));
+    checks.insert(
+        "database".to_string(),
+        match database {
+            Ok(Ok(_)) => json!({ "status": "ok", "latency_ms": latency_ms }),
+            // Текст ошибки (адрес базы, детали драйвера) только в лог: эндпоинт открыт без авторизации
+            Ok(Err(e)) => {
+                tracing::warn!("Readiness database check failed: {}", e);
+                json!({ "status": "fail", "latency_ms": latency_ms, "error": "unavailable" })
+            }
+            Err(_) => json!({ "status": "fail", "latency_ms": latency_ms, "error": "timeout" }),
+        },
+    );
+    ready &= database_ok;
+
+    // Без базы состояние миграций не узнать
+    if database_ok {
+        let started = Instant::now();
+        let migrations = tokio::time::timeout(timeout, db::schema_status(&state.pool)).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        // Миграции новее бинарника — обычное состояние при rolling deploy: старые поды
+        // продолжают обслуживать запросы, отказ возможен только при старте
+        let check = match migrations {
+            Ok(Ok(schema)) => json!({
+                "status": if schema.pending == 0 { "ok" } else { "fail" },
+                "latency_ms": latency_ms,
+                "pending": schema.pending,
+                "unknown": schema.unknown,
+            }),
+            Ok(Err(e)) => {
+                tracing::warn!("Readiness migrations check failed: {}", e);
+                json!({ "status": "fail", "latency_ms": latency_ms, "error": "unavailable" })
+            }
+            Err(_) => json!({ "status": "fail", "latency_ms": latency_ms, "error": "timeout" }),
+        };
+        ready &= check["status"] == "ok";
+        checks.insert("migrations".to_string(), check);
+    }
+
+    for (name, worker) in state.health.workers() {
+        ready &= worker.healthy;
+        checks.insert(
+            name.to_string(),
+            json!({
+                "status": if worker.healthy { "ok" } else { "fail" },
+                "last_beat_ms": worker.since_beat.map(|since| since.as_millis() as u64),
+            }),
+        );
+    }
+
+    let draining = state.health.is_draining();
+    let status = if draining {
+        "draining"
+    } else if ready {
+        "ok"
+    } else {
+        "fail"
+    };
+    let code = if ready && !draining {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (code, Json(json!({ "status": status, "checks": checks })))
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new()
+        .route("/", get(health))
+        .route("/live", get(live))
+        .route("/ready", get(ready))
+}
@@ -0,0 +1,24 @@
+use axum::{extract::State, routing::get, Router};
+use metrics_exporter_prometheus::PrometheusHandle;
+use sqlx::PgPool;
+
+use crate::services::metrics;
+
+#[derive(Clone)]
+struct MetricsState {
+    handle: PrometheusHandle,
+    pool: PgPool,
+}
+
+/// Текстовый формат Prometheus; пул снимается в момент опроса
+async fn render(State(state): State<MetricsState>) -> String {
+    metrics::record_pool(&state.pool);
+    state.handle.render()
+}
+
+/// Отдельный роутер для порта METRICS_PORT: без CORS, трассировки и авторизации
+pub fn router(handle: PrometheusHandle, pool: PgPool) -> Router {
+    Router::new()
+        .route("/metrics", get(render))
+        .with_state(MetricsState { handle, pool })
+}
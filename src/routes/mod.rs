@@ -1,3 +1,16 @@
 pub mod auth;
 pub mod game;
 pub mod claim;
+pub mod referral;
+pub mod task;
+pub mod daily;
+pub mod achievement;
+pub mod clan;
+pub mod tournament;
+pub mod admin;
+pub mod profile;
+pub mod realtime;
+pub mod bot;
+pub mod shop;
+pub mod health;
+pub mod metrics;
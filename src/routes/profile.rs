@@ -0,0 +1,88 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::Json,
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+
+use crate::app_state::AppState;
+use crate::models::user::{EnergyStatus, Profile, ProfileLeague, User};
+use crate::services::{energy, leagues};
+use crate::utils::auth::extract_user_id;
+use crate::utils::errors::AppError;
+
+/// Профиль игрока; всё собирается одним запросом к базе
+async fn get_profile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Profile>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let row = sqlx::query!(
+        r#"
+        SELECT u.id, u.telegram_id, u.username, u.first_name, u.last_name, u.created_at,
+            COALESCE(s.score, 0) AS "score!",
+            COALESCE(b.balance, 0) AS "balance!",
+            CASE WHEN s.score IS NULL THEN NULL
+                ELSE (SELECT COUNT(*) + 1 FROM scores o WHERE o.score > s.score)
+            END AS rank,
+            e.energy AS "energy?", e.updated_at AS "energy_updated_at?",
+            l.league AS "league?"
+        FROM users u
+        LEFT JOIN scores s ON s.user_id = u.id
+        LEFT JOIN balances b ON b.user_id = u.id
+        LEFT JOIN user_energy e ON e.user_id = u.id
+        LEFT JOIN user_leagues l ON l.user_id = u.id
+        WHERE u.id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    
+    let config = &state.config;
+    let score = row.score;
+    
+    let stored_energy = row.energy.zip(row.energy_updated_at);
+    let current_energy = energy::current(config, stored_energy, Utc::now());
+    
+    // Лига — сохранённое состояние, как в /game/league; без записи игрок ещё в первой лиге
+    let league_index = row.league
+        .as_deref()
+        .and_then(|name| leagues::find_league(config, name))
+        .map_or(0, |(index, _)| index);
+    let next = config.leagues.get(league_index + 1);
+    
+    Ok(Json(Profile {
+        user: User {
+            id: row.id,
+            telegram_id: row.telegram_id,
+            username: row.username,
+            first_name: row.first_name,
+            last_name: row.last_name,
+            created_at: row.created_at,
+        },
+        score,
+        balance: row.balance,
+        rank: row.rank,
+        energy: EnergyStatus {
+            current: current_energy,
+            max: config.energy_max,
+            regen_per_second: config.energy_regen_per_second,
+            seconds_to_full: energy::seconds_to_full(config, current_energy),
+        },
+        league: ProfileLeague {
+            league: config.leagues[league_index].name.clone(),
+            next_league: next.map(|l| l.name.clone()),
+            next_league_score: next.map(|l| l.min_score),
+        },
+    }))
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new()
+        .route("/", get(get_profile))
+}
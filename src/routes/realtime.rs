@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::HeaderMap,
+    response::Response,
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::Instant;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::models::event::AppEvent;
+use crate::models::realtime::{ClientMessage, ServerEvent};
+use crate::services::energy;
+use crate::services::events::BusMessage;
+use crate::services::metrics;
+use crate::services::score::ScoreUpdate;
+use crate::utils::auth::extract_user_id_or_token;
+use crate::utils::errors::AppError;
+
+#[derive(Debug, Deserialize)]
+pub struct WsParams {
+    /// JWT токен (браузерный WebSocket не передаёт заголовок Authorization)
+    pub token: Option<String>,
+    /// resume_token из `welcome` предыдущего соединения
+    pub resume: Option<Uuid>,
+    /// Последнее полученное событие; более поздние будут отправлены повторно
+    #[serde(default)]
+    pub last_event_id: u64,
+}
+
+async fn ws_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<WsParams>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let user_id = extract_user_id_or_token(&headers, params.token.as_deref(), &state.config.jwt_secret)?;
+
+    // Сессия живёт в отдельной задаче: переносим в неё span запроса с request_id и user_id
+    let span = tracing::Span::current();
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, user_id, params).instrument(span)))
+}
+
+/// Через сколько перечитать состояние, если пуш не влез в очередь или события шины потерялись
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Последнее отправленное клиенту состояние: пушим только изменения
+#[derive(Default)]
+struct Watched {
+    initialized: bool,
+    rank: Option<i64>,
+    /// Счёт игрока: по нему видно, обогнал ли его игрок из события шины
+    score: Option<i32>,
+    claims: HashMap<Uuid, String>,
+    /// Клиент мог не получить изменения: при следующей проверке перечитать и заявки
+    stale: bool,
+    energy_full: bool,
+    /// Ближайшая проверка из базы: восстановление энергии или повтор после потери пуша
+    refresh_at: Option<Instant>,
+}
+
+struct Connection {
+    state: AppState,
+    user_id: Uuid,
+    token: Uuid,
+    outgoing: mpsc::Sender<Message>,
+    watched: Watched,
+}
+
+async fn handle_socket(socket: WebSocket, state: AppState, user_id: Uuid, params: WsParams) {
+    let attached = state.ws_sessions.attach(user_id, params.resume, params.last_event_id);
+    let (mut sink, mut stream) = socket.split();
+
+    // Ограниченная очередь на отправку: медленный клиент не копит сообщения в памяти без предела
+    let (outgoing, mut queue) = mpsc::channel::<Message>(state.config.ws_send_buffer.max(1));
+    let writer = tokio::spawn(async move {
+        while let Some(message) = queue.recv().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    tracing::info!("WebSocket connected: user_id={}, resumed={}", user_id, attached.resumed);
+
+    let mut bus = state.events.subscribe();
+    // Изменения приходят через шину; периодическая проверка — редкая страховка
+    let fallback_period = Duration::from_secs(state.config.ws_poll_interval_secs.max(1));
+    let mut fallback = tokio::time::interval_at(Instant::now() + fallback_period, fallback_period);
+    let mut conn = Connection {
+        state: state.clone(),
+        user_id,
+        token: attached.token,
+        outgoing,
+        watched: Watched::default(),
+    };
+
+    let welcome = ServerEvent::Welcome {
+        resume_token: attached.token,
+        resumed: attached.resumed,
+        last_tap_seq: attached.last_tap_seq,
+    };
+    let mut open = conn.send_json(&welcome).await;
+    for envelope in &attached.replay {
+        if !open {
+            break;
+        }
+        open = conn.send_json(envelope).await;
+    }
+    if open {
+        if let Err(e) = conn.refresh().await {
+            tracing::error!("WebSocket refresh failed: user_id={}, error={}", user_id, e);
+        }
+    }
+
+    while open {
+        let refresh_at = conn.watched.refresh_at;
+        tokio::select! {
+            incoming = stream.next() => match incoming {
+                Some(Ok(Message::Text(text))) => open = conn.handle_text(&text).await,
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => open = false,
+                Some(Ok(_)) => {}
+            },
+            message = bus.recv() => {
+                let message = match message {
+                    Ok(message) => message,
+                    Err(broadcast::error::RecvError::Lagged(_)) => BusMessage::Resync,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Err(e) = conn.handle_event(message).await {
+                    tracing::error!("WebSocket event failed: user_id={}, error={}", user_id, e);
+                }
+            }
+            _ = tokio::time::sleep_until(refresh_at.unwrap_or_else(Instant::now)), if refresh_at.is_some() => {
+                if let Err(e) = conn.refresh().await {
+                    tracing::error!("WebSocket refresh failed: user_id={}, error={}", user_id, e);
+                }
+            }
+            _ = fallback.tick() => {
+                if let Err(e) = conn.refresh().await {
+                    tracing::error!("WebSocket refresh failed: user_id={}, error={}", user_id, e);
+                }
+            }
+            _ = state.shutdown.cancelled() => {
+                // 1012 Service Restart: клиент переподключается к другому инстансу
+                let close = CloseFrame { code: 1012, reason: "server restart".into() };
+                let _ = conn.outgoing.send(Message::Close(Some(close))).await;
+                open = false;
+            }
+        }
+    }
+
+    drop(conn);
+    let _ = writer.await;
+    state.ws_sessions.detach(attached.token);
+
+    tracing::info!("WebSocket disconnected: user_id={}", user_id);
+}
+
+impl Connection {
+    async fn send_json<T: serde::Serialize>(&self, value: &T) -> bool {
+        let text = serde_json::to_string(value).unwrap_or_default();
+        self.outgoing.send(Message::Text(text)).await.is_ok()
+    }
+
+    /// Ответ на сообщение клиента: ждёт места в очереди, поэтому пока клиент
+    /// не читает ответы, следующие пачки тапов не обрабатываются
+    async fn push(&self, event: ServerEvent) -> bool {
+        let envelope = self.state.ws_sessions.record(self.token, event);
+        self.send_json(&envelope).await
+    }
+
+    /// Пуш изменения состояния: при заполненной очереди пропускается
+    /// и будет отправлен при повторной проверке через `RETRY_DELAY`
+    fn offer(&mut self, event: ServerEvent) -> bool {
+        let Ok(permit) = self.outgoing.try_reserve() else {
+            self.watched.stale = true;
+            self.refresh_after(RETRY_DELAY);
+            return false;
+        };
+        let envelope = self.state.ws_sessions.record(self.token, event);
+        permit.send(Message::Text(serde_json::to_string(&envelope).unwrap_or_default()));
+        true
+    }
+
+    /// Возвращает false, если соединение нужно закрыть
+    async fn handle_text(&mut self, text: &str) -> bool {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => {
+                return self.push(ServerEvent::Error {
+                    message: format!("Invalid message: {}", e),
+                })
+                .await
+            }
+        };
+        
+        match message {
+            ClientMessage::Taps { seq, count } => self.apply_taps(seq, count).await,
+        }
+    }
+
+    async fn apply_taps(&mut self, seq: u64, count: i32) -> bool {
+        // Пачка уже применена до переподключения
+        if seq <= self.state.ws_sessions.last_tap_seq(self.token) {
+            return true;
+        }
+        if count <= 0 {
+            return self.push(ServerEvent::Error {
+                message: "Tap count must be positive".to_string(),
+            })
+            .await;
+        }
+        
+        let outcome = match self.state.scores.apply(&self.state.config, self.user_id, ScoreUpdate::Taps(count)).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                tracing::error!("Failed to apply taps: user_id={}, error={}", self.user_id, e);
+                return self.push(ServerEvent::Error {
+                    message: "Failed to apply taps".to_string(),
+                })
+                .await;
+            }
+        };
+        
+        self.state.ws_sessions.set_last_tap_seq(self.token, seq);
+        metrics::record_taps("ws", outcome.requested, outcome.gained);
+        self.watched.energy_full = outcome.energy >= self.state.config.energy_max;
+        self.schedule_energy_check(outcome.energy);
+        
+        self.push(ServerEvent::TapsApplied {
+            seq,
+            accepted: outcome.gained,
+            score: outcome.score,
+            energy: outcome.energy,
+        })
+        .await
+    }
+
+    /// События шины от всех инстансов
+    async fn handle_event(&mut self, message: BusMessage) -> Result<(), sqlx::Error> {
+        match message {
+            BusMessage::Event(AppEvent::ClaimStatusChanged { user_id, claim_id, status, .. }) if user_id == self.user_id => {
+                self.push_claim_status(claim_id, status);
+            }
+            BusMessage::Event(AppEvent::ScoreChanged { user_id, score, previous_score }) => {
+                // Своё место пересчитываем и при своих очках, и когда нас обогнали (в том числе
+                // игрок, подключённый к другому инстансу)
+                let overtaken = self.watched.score.is_some_and(|mine| overtakes(previous_score, score, mine));
+                if user_id == self.user_id || overtaken {
+                    self.check_rank().await?;
+                }
+            }
+            BusMessage::Event(_) => {}
+            BusMessage::Resync => {
+                self.watched.stale = true;
+                self.refresh_after(RETRY_DELAY);
+            }
+        }
+        Ok(())
+    }
+
+    fn push_claim_status(&mut self, claim_id: Uuid, status: String) {
+        if self.watched.claims.get(&claim_id) == Some(&status) {
+            return;
+        }
+        let event = ServerEvent::ClaimStatus {
+            claim_id,
+            status: status.clone(),
+        };
+        if self.offer(event) {
+            self.watched.claims.insert(claim_id, status);
+        }
+    }
+
+    async fn check_rank(&mut self) -> Result<(), sqlx::Error> {
+        let row = sqlx::query!(
+            r#"
+            SELECT s.score, (SELECT COUNT(*) + 1 FROM scores o WHERE o.score > s.score) AS "rank!"
+            FROM scores s
+            WHERE s.user_id = $1
+            "#,
+            self.user_id
+        )
+        .fetch_optional(&self.state.pool)
+        .await?;
+        
+        if let Some(row) = row {
+            self.watched.score = Some(row.score);
+            self.push_rank(row.rank);
+        }
+        Ok(())
+    }
+
+    fn push_rank(&mut self, rank: i64) {
+        if self.watched.rank == Some(rank) {
+            return;
+        }
+        let event = ServerEvent::Rank {
+            rank,
+            previous_rank: self.watched.rank,
+        };
+        if self.offer(event) {
+            self.watched.rank = Some(rank);
+        }
+    }
+
+    fn refresh_after(&mut self, delay: Duration) {
+        let at = Instant::now() + delay;
+        self.watched.refresh_at = Some(self.watched.refresh_at.map_or(at, |current| current.min(at)));
+    }
+
+    /// Проверка к моменту, когда энергия восстановится полностью
+    fn schedule_energy_check(&mut self, energy: i32) {
+        let seconds = energy::seconds_to_full(&self.state.config, energy);
+        if seconds > 0 {
+            self.refresh_after(Duration::from_secs(seconds as u64));
+        }
+    }
+
+    /// Состояние из базы: при подключении, к восстановлению энергии, после потерянных событий
+    /// или пушей и раз в `ws_poll_interval_secs` на случай изменений в обход шины.
+    async fn refresh(&mut self) -> Result<(), sqlx::Error> {
+        self.watched.refresh_at = None;
+
+        let snapshot = sqlx::query!(
+            r#"
+            SELECT
+                s.score AS "score?",
+                (SELECT COUNT(*) + 1 FROM scores o WHERE o.score > s.score) AS rank,
+                e.energy AS "energy?", e.updated_at AS "energy_updated_at?"
+            FROM users u
+            LEFT JOIN scores s ON s.user_id = u.id
+            LEFT JOIN user_energy e ON e.user_id = u.id
+            WHERE u.id = $1
+            "#,
+            self.user_id
+        )
+        .fetch_optional(&self.state.pool)
+        .await?;
+        let Some(snapshot) = snapshot else {
+            return Ok(());
+        };
+        
+        let config = &self.state.config;
+        let current_energy = energy::current(config, snapshot.energy.zip(snapshot.energy_updated_at), Utc::now());
+        let energy_full = current_energy >= config.energy_max;
+        
+        if !self.watched.initialized || self.watched.stale {
+            let claims = sqlx::query!(
+                r#"
+                SELECT id, status
+                FROM claims
+                WHERE user_id = $1
+                ORDER BY created_at DESC
+                LIMIT 20
+                "#,
+                self.user_id
+            )
+            .fetch_all(&self.state.pool)
+            .await?;
+            
+            self.watched.stale = false;
+            if self.watched.initialized {
+                for claim in claims {
+                    self.push_claim_status(claim.id, claim.status);
+                }
+            } else {
+                self.watched.claims = claims.into_iter().map(|c| (c.id, c.status)).collect();
+            }
+        }
+        
+        if !self.watched.initialized {
+            // Первая проверка задаёт исходное состояние; место сообщаем сразу
+            self.watched.energy_full = energy_full;
+            self.watched.initialized = true;
+        } else if energy_full && !self.watched.energy_full {
+            self.watched.energy_full = self.offer(ServerEvent::EnergyRefilled { energy: current_energy });
+        } else if !energy_full {
+            self.watched.energy_full = false;
+        }
+        self.schedule_energy_check(current_energy);
+        
+        self.watched.score = snapshot.score;
+        if let Some(rank) = snapshot.rank {
+            self.push_rank(rank);
+        }
+        
+        Ok(())
+    }
+}
+
+/// Игрок со счётом `previous` → `score` обошёл игрока со счётом `mine`: место считается
+/// по строго большим счетам, поэтому равный счёт ещё не обгон
+fn overtakes(previous: i32, score: i32, mine: i32) -> bool {
+    previous <= mine && score > mine
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new().route("/", get(ws_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use crate::repos::memory::InMemoryRepo;
+
+    /// Соединение с очередью на одно сообщение и без базы
+    fn connection() -> (Connection, mpsc::Receiver<Message>) {
+        let state = AppState::for_tests(Arc::new(InMemoryRepo::new()));
+        let user_id = Uuid::new_v4();
+        let token = state.ws_sessions.attach(user_id, None, 0).token;
+        let (outgoing, queue) = mpsc::channel(1);
+        let conn = Connection {
+            state,
+            user_id,
+            token,
+            outgoing,
+            watched: Watched::default(),
+        };
+        (conn, queue)
+    }
+
+    #[tokio::test]
+    async fn full_queue_defers_state_pushes_to_a_refresh() {
+        let (mut conn, mut queue) = connection();
+        conn.push_rank(5);
+        assert_eq!(conn.watched.rank, Some(5));
+        assert!(conn.watched.refresh_at.is_none());
+
+        // Клиент не читает: пуш не копится, а откладывается до перечитывания состояния
+        let claim_id = Uuid::new_v4();
+        conn.push_rank(3);
+        conn.push_claim_status(claim_id, "completed".to_string());
+        assert_eq!(conn.watched.rank, Some(5));
+        assert!(!conn.watched.claims.contains_key(&claim_id));
+        assert!(conn.watched.stale);
+        let refresh_at = conn.watched.refresh_at.unwrap();
+        assert!(refresh_at <= Instant::now() + RETRY_DELAY);
+
+        // Освободившееся место занимает актуальное состояние, номера событий идут подряд
+        let Some(Message::Text(first)) = queue.recv().await else { panic!("expected text") };
+        conn.push_rank(3);
+        let Some(Message::Text(second)) = queue.recv().await else { panic!("expected text") };
+        let first: serde_json::Value = serde_json::from_str(&first).unwrap();
+        let second: serde_json::Value = serde_json::from_str(&second).unwrap();
+        assert_eq!((first["id"].as_u64(), first["rank"].as_i64()), (Some(1), Some(5)));
+        assert_eq!((second["id"].as_u64(), second["rank"].as_i64()), (Some(2), Some(3)));
+        assert_eq!(second["previous_rank"], 5);
+    }
+
+    #[tokio::test]
+    async fn energy_check_is_scheduled_for_full_refill() {
+        let (mut conn, _queue) = connection();
+        let max = conn.state.config.energy_max;
+        conn.schedule_energy_check(max);
+        assert!(conn.watched.refresh_at.is_none());
+
+        conn.schedule_energy_check(max - 10);
+        let seconds = energy::seconds_to_full(&conn.state.config, max - 10) as u64;
+        let refresh_at = conn.watched.refresh_at.unwrap();
+        assert!(refresh_at <= Instant::now() + Duration::from_secs(seconds));
+        assert!(refresh_at > Instant::now() + Duration::from_secs(seconds) - Duration::from_secs(1));
+
+        // Более ранняя проверка не откладывается поздней
+        conn.refresh_after(RETRY_DELAY);
+        conn.schedule_energy_check(0);
+        assert!(conn.watched.refresh_at.unwrap() <= Instant::now() + RETRY_DELAY);
+    }
+
+    #[test]
+    fn overtaking_requires_crossing_from_at_most_to_above() {
+        assert!(overtakes(50, 150, 100));
+        assert!(overtakes(100, 101, 100));
+        // Уже был выше или ещё не догнал
+        assert!(!overtakes(120, 150, 100));
+        assert!(!overtakes(50, 100, 100));
+        assert!(!overtakes(10, 90, 100));
+    }
+}
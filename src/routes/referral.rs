@@ -0,0 +1,104 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::Json,
+    routing::get,
+    Router,
+};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::app_state::AppState;
+use crate::models::referral::{Invitee, LevelEarnings};
+use crate::utils::auth::extract_user_id;
+use crate::utils::errors::AppError;
+
+#[derive(Debug, Serialize)]
+pub struct ReferralsResponse {
+    pub referral_code: String,
+    pub invite_link: Option<String>,
+    pub invitees_count: usize,
+    pub total_earned: Decimal,
+    pub earnings_by_level: Vec<LevelEarnings>,
+    pub invitees: Vec<Invitee>,
+}
+
+async fn referrals(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ReferralsResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let user = sqlx::query!(
+        r#"SELECT referral_code FROM users WHERE id = $1"#,
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    
+    let invitees = sqlx::query!(
+        r#"
+        SELECT
+            u.id AS user_id,
+            u.username,
+            u.first_name,
+            u.referred_at,
+            COALESCE(SUM(r.amount), 0) AS "earned!"
+        FROM users u
+        LEFT JOIN referral_rewards r
+            ON r.referee_id = u.id AND r.referrer_id = $1 AND r.level = 1
+        WHERE u.referred_by = $1
+        GROUP BY u.id
+        ORDER BY u.referred_at DESC NULLS LAST
+        "#,
+        user_id
+    )
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|row| Invitee {
+        user_id: row.user_id,
+        username: row.username,
+        first_name: row.first_name,
+        joined_at: row.referred_at,
+        earned: row.earned,
+    })
+    .collect::<Vec<_>>();
+    
+    let earnings_by_level = sqlx::query!(
+        r#"
+        SELECT level, SUM(amount) AS "amount!"
+        FROM referral_rewards
+        WHERE referrer_id = $1
+        GROUP BY level
+        ORDER BY level
+        "#,
+        user_id
+    )
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|row| LevelEarnings {
+        level: row.level,
+        amount: row.amount,
+    })
+    .collect::<Vec<_>>();
+    
+    let total_earned = earnings_by_level.iter().map(|e| e.amount).sum();
+    let invite_link = state.config.telegram_webapp_url.as_ref()
+        .map(|url| format!("{}?startapp=ref_{}", url, user.referral_code));
+    
+    Ok(Json(ReferralsResponse {
+        referral_code: user.referral_code,
+        invite_link,
+        invitees_count: invitees.len(),
+        total_earned,
+        earnings_by_level,
+        invitees,
+    }))
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new().route("/", get(referrals))
+}
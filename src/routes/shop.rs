@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+
+use crate::app_state::AppState;
+use crate::models::bot::{CreateInvoiceLink, LabeledPrice};
+use crate::models::shop::{InvoiceResponse, ShopItem};
+use crate::services::payments;
+use crate::utils::auth::extract_user_id;
+use crate::utils::errors::AppError;
+
+async fn list_items(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ShopItem>>, AppError> {
+    let items = sqlx::query_as!(
+        ShopItem,
+        r#"
+        SELECT id, kind, title, description, price_stars, amount
+        FROM shop_items
+        WHERE is_active
+        ORDER BY sort_order, id
+        "#
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    
+    Ok(Json(items))
+}
+
+/// Создаёт заказ и ссылку на инвойс в Telegram Stars; Mini App открывает её через openInvoice
+async fn create_invoice(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(item_id): Path<String>,
+) -> Result<Json<InvoiceResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let mut conn = state.pool.acquire().await?;
+    let (order_id, item) = payments::create_order(&mut conn, user_id, &item_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Shop item not found".to_string()))?;
+    drop(conn);
+    
+    let invoice = CreateInvoiceLink {
+        title: item.title.clone(),
+        description: item.description.clone(),
+        payload: order_id.to_string(),
+        provider_token: String::new(),
+        currency: payments::STARS_CURRENCY.to_string(),
+        prices: vec![LabeledPrice {
+            label: item.title,
+            amount: i64::from(item.price_stars),
+        }],
+    };
+    let invoice_link = state
+        .bot_api
+        .create_invoice_link(&invoice)
+        .await
+        .map_err(|e| AppError::Internal(e.into()))?;
+    
+    tracing::info!("Stars order created: order_id={}, user_id={}, item_id={}", order_id, user_id, item_id);
+    
+    Ok(Json(InvoiceResponse {
+        order_id,
+        invoice_link,
+    }))
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new()
+        .route("/", get(list_items))
+        .route("/:id/invoice", post(create_invoice))
+}
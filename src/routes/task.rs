@@ -0,0 +1,282 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::app_state::AppState;
+use crate::models::task::{Task, TaskView};
+use crate::services::balance::{self, LedgerKind};
+use crate::services::tasks::{self, TaskContext, TaskVerifier, Verification};
+use crate::utils::auth::extract_user_id;
+use crate::utils::errors::AppError;
+
+#[derive(Debug, Serialize)]
+pub struct VerifyTaskResponse {
+    pub completed: bool,
+    pub status: String,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClaimTaskResponse {
+    pub success: bool,
+    pub reward: Decimal,
+}
+
+async fn load_task(state: &AppState, task_id: &str) -> Result<(Task, &'static dyn TaskVerifier), AppError> {
+    let task = sqlx::query_as!(
+        Task,
+        r#"
+        SELECT id, kind, title, description, reward, params
+        FROM tasks
+        WHERE id = $1 AND is_active
+        "#,
+        task_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Task not found".to_string()))?;
+
+    let verifier = tasks::verifier_for(&task.kind)
+        .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Unknown task kind: {}", task.kind)))?;
+
+    Ok((task, verifier))
+}
+
+async fn list_tasks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<TaskView>>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+
+    // Разовые задания хранятся с period = '', ежедневные — с датой текущего дня
+    let today = tasks::daily_period(&state.config);
+    let rows = sqlx::query!(
+        r#"
+        SELECT t.id, t.kind, t.title, t.description, t.reward, t.params,
+            ut.status AS "status?"
+        FROM tasks t
+        LEFT JOIN user_tasks ut
+            ON ut.task_id = t.id AND ut.user_id = $1 AND ut.period IN ('', $2)
+        WHERE t.is_active
+        ORDER BY t.sort_order, t.id
+        "#,
+        user_id,
+        today
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let views = rows
+        .into_iter()
+        .filter_map(|row| {
+            let Some(verifier) = tasks::verifier_for(&row.kind) else {
+                tracing::warn!("Skipping task {} with unknown kind {}", row.id, row.kind);
+                return None;
+            };
+
+            Some(TaskView {
+                task: Task {
+                    id: row.id,
+                    kind: row.kind,
+                    title: row.title,
+                    description: row.description,
+                    reward: row.reward,
+                    params: row.params,
+                },
+                status: row.status.unwrap_or_else(|| "available".to_string()),
+                daily: verifier.is_daily(),
+            })
+        })
+        .collect();
+
+    Ok(Json(views))
+}
+
+async fn start_task(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(task_id): Path<String>,
+) -> Result<Json<VerifyTaskResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    let (task, verifier) = load_task(&state, &task_id).await?;
+
+    let status = sqlx::query_scalar!(
+        r#"
+        INSERT INTO user_tasks (user_id, task_id, period, status)
+        VALUES ($1, $2, $3, 'started')
+        ON CONFLICT (user_id, task_id, period) DO UPDATE SET status = user_tasks.status
+        RETURNING status
+        "#,
+        user_id,
+        task.id,
+        tasks::current_period(verifier, &state.config)
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    Ok(Json(VerifyTaskResponse {
+        completed: status != "started",
+        status,
+        message: None,
+    }))
+}
+
+/// Проверяет выполнение задания и отмечает его выполненным
+async fn verify_task(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(task_id): Path<String>,
+) -> Result<Json<VerifyTaskResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    let (task, verifier) = load_task(&state, &task_id).await?;
+    let period = tasks::current_period(verifier, &state.config);
+
+    let progress = sqlx::query!(
+        r#"
+        SELECT status, started_at FROM user_tasks
+        WHERE user_id = $1 AND task_id = $2 AND period = $3
+        "#,
+        user_id,
+        task.id,
+        period
+    )
+    .fetch_optional(&state.pool)
+    .await?;
+
+    if let Some(ref progress) = progress {
+        if progress.status != "started" {
+            return Ok(Json(VerifyTaskResponse {
+                completed: true,
+                status: progress.status.clone(),
+                message: None,
+            }));
+        }
+    }
+
+    let telegram_id = sqlx::query_scalar!(
+        r#"SELECT telegram_id FROM users WHERE id = $1"#,
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    let status = if progress.is_some() { "started" } else { "available" };
+    let ctx = TaskContext {
+        pool: &state.pool,
+        config: &state.config,
+        bot_api: &state.bot_api,
+        user_id,
+        telegram_id,
+        params: &task.params,
+        started_at: progress.map(|p| p.started_at),
+    };
+
+    match verifier.verify(&ctx).await? {
+        Verification::Completed => {
+            sqlx::query!(
+                r#"
+                INSERT INTO user_tasks (user_id, task_id, period, status, completed_at)
+                VALUES ($1, $2, $3, 'completed', now())
+                ON CONFLICT (user_id, task_id, period)
+                DO UPDATE SET status = 'completed', completed_at = now()
+                WHERE user_tasks.status = 'started'
+                "#,
+                user_id,
+                task.id,
+                period
+            )
+            .execute(&state.pool)
+            .await?;
+
+            Ok(Json(VerifyTaskResponse {
+                completed: true,
+                status: "completed".to_string(),
+                message: None,
+            }))
+        }
+        Verification::Pending(message) => Ok(Json(VerifyTaskResponse {
+            completed: false,
+            status: status.to_string(),
+            message: Some(message),
+        })),
+    }
+}
+
+/// Забирает награду за выполненное задание
+async fn claim_task(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(task_id): Path<String>,
+) -> Result<Json<ClaimTaskResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    let (task, verifier) = load_task(&state, &task_id).await?;
+    let period = tasks::current_period(verifier, &state.config);
+
+    let mut tx = state.pool.begin().await?;
+
+    let status = sqlx::query_scalar!(
+        r#"
+        SELECT status FROM user_tasks
+        WHERE user_id = $1 AND task_id = $2 AND period = $3
+        FOR UPDATE
+        "#,
+        user_id,
+        task.id,
+        period
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    match status.as_deref() {
+        Some("completed") => {}
+        Some("claimed") => return Err(AppError::Validation("Task reward already claimed".to_string())),
+        _ => return Err(AppError::Validation("Task is not completed".to_string())),
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE user_tasks
+        SET status = 'claimed', claimed_at = now()
+        WHERE user_id = $1 AND task_id = $2 AND period = $3
+        "#,
+        user_id,
+        task.id,
+        period
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    balance::credit_earning(
+        &mut tx,
+        &state.config,
+        user_id,
+        task.reward,
+        LedgerKind::TaskReward,
+        &format!("{}:{}", task.id, period),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!("Task reward claimed: user_id={}, task_id={}, reward={}", user_id, task.id, task.reward);
+
+    Ok(Json(ClaimTaskResponse {
+        success: true,
+        reward: task.reward,
+    }))
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new()
+        .route("/", get(list_tasks))
+        .route("/:id/start", post(start_task))
+        .route("/:id/verify", post(verify_task))
+        .route("/:id/claim", post(claim_task))
+}
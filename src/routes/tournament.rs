@@ -0,0 +1,177 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::app_state::AppState;
+use crate::models::tournament::{Tournament, TournamentStanding};
+use crate::services::tournaments::{self, JoinResult};
+use crate::utils::auth::extract_user_id;
+use crate::utils::errors::AppError;
+
+#[derive(Debug, Serialize)]
+pub struct TournamentResponse {
+    #[serde(flatten)]
+    pub tournament: Tournament,
+    pub participants: i64,
+    /// Текущие места для открытого турнира или архив итогов для закрытого
+    pub standings: Vec<TournamentStanding>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JoinTournamentResponse {
+    pub success: bool,
+    pub tournament_id: Uuid,
+}
+
+pub(crate) async fn load_tournament(state: &AppState, tournament_id: Uuid) -> Result<Tournament, AppError> {
+    sqlx::query_as!(
+        Tournament,
+        r#"
+        SELECT id, title, starts_at, ends_at, entry_fee, min_league, prizes, status, closed_at
+        FROM tournaments
+        WHERE id = $1
+        "#,
+        tournament_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Tournament not found".to_string()))
+}
+
+/// Открытые турниры и закрытые за последнюю неделю
+async fn list_tournaments(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<Tournament>>, AppError> {
+    let tournaments = sqlx::query_as!(
+        Tournament,
+        r#"
+        SELECT id, title, starts_at, ends_at, entry_fee, min_league, prizes, status, closed_at
+        FROM tournaments
+        WHERE status = 'open' OR closed_at > now() - interval '7 days'
+        ORDER BY status DESC, starts_at
+        "#
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    
+    Ok(Json(tournaments))
+}
+
+async fn get_tournament(
+    State(state): State<AppState>,
+    Path(tournament_id): Path<Uuid>,
+) -> Result<Json<TournamentResponse>, AppError> {
+    let tournament = load_tournament(&state, tournament_id).await?;
+    
+    let participants = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM tournament_entries WHERE tournament_id = $1"#,
+        tournament_id
+    )
+    .fetch_one(&state.pool)
+    .await?;
+    
+    let standings = if tournament.status == "closed" {
+        sqlx::query!(
+            r#"
+            SELECT r.rank, r.user_id, u.username, u.first_name, r.score, r.prize
+            FROM tournament_results r
+            JOIN users u ON u.id = r.user_id
+            WHERE r.tournament_id = $1
+            ORDER BY r.rank
+            LIMIT 100
+            "#,
+            tournament_id
+        )
+        .fetch_all(&state.pool)
+        .await?
+        .into_iter()
+        .map(|row| TournamentStanding {
+            rank: row.rank.into(),
+            user_id: row.user_id,
+            username: row.username,
+            first_name: row.first_name,
+            score: row.score,
+            prize: Some(row.prize),
+        })
+        .collect()
+    } else {
+        sqlx::query!(
+            r#"
+            SELECT
+                ROW_NUMBER() OVER (ORDER BY e.score DESC, e.updated_at, e.joined_at) AS "rank!",
+                e.user_id, u.username, u.first_name, e.score
+            FROM tournament_entries e
+            JOIN users u ON u.id = e.user_id
+            WHERE e.tournament_id = $1
+            ORDER BY 1
+            LIMIT 100
+            "#,
+            tournament_id
+        )
+        .fetch_all(&state.pool)
+        .await?
+        .into_iter()
+        .map(|row| TournamentStanding {
+            rank: row.rank,
+            user_id: row.user_id,
+            username: row.username,
+            first_name: row.first_name,
+            score: row.score,
+            prize: None,
+        })
+        .collect()
+    };
+    
+    Ok(Json(TournamentResponse {
+        tournament,
+        participants,
+        standings,
+    }))
+}
+
+async fn join_tournament(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(tournament_id): Path<Uuid>,
+) -> Result<Json<JoinTournamentResponse>, AppError> {
+    let user_id = extract_user_id(&headers, &state.config.jwt_secret)?;
+    
+    let mut tx = state.pool.begin().await?;
+    
+    match tournaments::join(&mut tx, &state.config, user_id, tournament_id).await? {
+        JoinResult::Joined => {}
+        JoinResult::NotFound => return Err(AppError::NotFound("Tournament not found".to_string())),
+        JoinResult::Closed => return Err(AppError::Validation("Tournament is closed".to_string())),
+        JoinResult::AlreadyJoined => {
+            return Err(AppError::Validation("Already joined this tournament".to_string()))
+        }
+        JoinResult::NotEligible => {
+            return Err(AppError::Validation("Your league is too low for this tournament".to_string()))
+        }
+        JoinResult::InsufficientBalance => {
+            return Err(AppError::Validation("Insufficient balance for the entry fee".to_string()))
+        }
+    }
+    
+    tx.commit().await?;
+    
+    tracing::info!("Tournament joined: user_id={}, tournament_id={}", user_id, tournament_id);
+    
+    Ok(Json(JoinTournamentResponse {
+        success: true,
+        tournament_id,
+    }))
+}
+
+pub fn router() -> Router<crate::app_state::AppState> {
+    Router::new()
+        .route("/", get(list_tournaments))
+        .route("/:id", get(get_tournament))
+        .route("/:id/join", post(join_tournament))
+}
@@ -0,0 +1,98 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::achievement::UnlockedAchievement;
+use crate::services::balance::{self, LedgerKind};
+
+/// Игровые метрики, по которым срабатывают правила из таблицы achievements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Score,
+    DailyStreak,
+    Referrals,
+    Withdrawals,
+}
+
+impl Metric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::Score => "score",
+            Metric::DailyStreak => "daily_streak",
+            Metric::Referrals => "referrals",
+            Metric::Withdrawals => "withdrawals",
+        }
+    }
+}
+
+/// Проверяет правила для новой величины метрики и открывает достигнутые достижения.
+/// Каждое достижение открывается один раз, награда начисляется на баланс.
+pub async fn record(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    metric: Metric,
+    value: i64,
+) -> Result<Vec<UnlockedAchievement>, sqlx::Error> {
+    let candidates = sqlx::query!(
+        r#"
+        SELECT a.id, a.title, a.reward
+        FROM achievements a
+        WHERE a.is_active
+            AND a.metric = $1
+            AND a.threshold <= $2
+            AND NOT EXISTS (
+                SELECT 1 FROM user_achievements ua
+                WHERE ua.user_id = $3 AND ua.achievement_id = a.id
+            )
+        ORDER BY a.sort_order, a.id
+        "#,
+        metric.as_str(),
+        value,
+        user_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut unlocked = Vec::new();
+    for achievement in candidates {
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO user_achievements (user_id, achievement_id, reward)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, achievement_id) DO NOTHING
+            "#,
+            user_id,
+            achievement.id,
+            achievement.reward
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected()
+            > 0;
+
+        if !inserted {
+            continue;
+        }
+
+        balance::credit_earning(
+            &mut *conn,
+            config,
+            user_id,
+            achievement.reward,
+            LedgerKind::AchievementReward,
+            &achievement.id,
+        )
+        .await?;
+
+        tracing::info!("Achievement unlocked: user_id={}, achievement_id={}", user_id, achievement.id);
+
+        unlocked.push(UnlockedAchievement {
+            id: achievement.id,
+            title: achievement.title,
+            reward: achievement.reward,
+        });
+    }
+
+    Ok(unlocked)
+}
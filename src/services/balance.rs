@@ -0,0 +1,240 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::services::referral;
+
+/// Тип операции в журнале баланса
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerKind {
+    ScoreEarning,
+    ReferralBonus,
+    ReferralPayout,
+    TaskReward,
+    DailyReward,
+    AchievementReward,
+    PassiveIncome,
+    PassiveUpgrade,
+    LeaguePromotion,
+    TournamentEntry,
+    TournamentPrize,
+    StarsPurchase,
+    StarsRefund,
+}
+
+impl LedgerKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerKind::ScoreEarning => "score_earning",
+            LedgerKind::ReferralBonus => "referral_bonus",
+            LedgerKind::ReferralPayout => "referral_payout",
+            LedgerKind::TaskReward => "task_reward",
+            LedgerKind::DailyReward => "daily_reward",
+            LedgerKind::AchievementReward => "achievement_reward",
+            LedgerKind::PassiveIncome => "passive_income",
+            LedgerKind::PassiveUpgrade => "passive_upgrade",
+            LedgerKind::LeaguePromotion => "league_promotion",
+            LedgerKind::TournamentEntry => "tournament_entry",
+            LedgerKind::TournamentPrize => "tournament_prize",
+            LedgerKind::StarsPurchase => "stars_purchase",
+            LedgerKind::StarsRefund => "stars_refund",
+        }
+    }
+}
+
+/// Начисляет монеты на баланс игрока.
+///
+/// Начисление идемпотентно по (user_id, kind, reference): повторный вызов
+/// ничего не меняет и возвращает `false`.
+pub async fn credit(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    amount: Decimal,
+    kind: LedgerKind,
+    reference: &str,
+) -> Result<bool, sqlx::Error> {
+    if amount <= Decimal::ZERO {
+        return Ok(false);
+    }
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO balance_transactions (id, user_id, amount, kind, reference)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, kind, reference) DO NOTHING
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        amount,
+        kind.as_str(),
+        reference
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if !inserted {
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO balances (user_id, balance)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id)
+        DO UPDATE SET
+            balance = balances.balance + EXCLUDED.balance,
+            updated_at = now()
+        "#,
+        user_id,
+        amount
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
+
+/// Начисляет игровой заработок и отдаёт процент пригласившим по цепочке
+pub async fn credit_earning(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    amount: Decimal,
+    kind: LedgerKind,
+    reference: &str,
+) -> Result<bool, sqlx::Error> {
+    let credited = credit(&mut *conn, user_id, amount, kind, reference).await?;
+
+    if credited {
+        referral::pay_uplines(&mut *conn, config, user_id, amount, kind, reference).await?;
+    }
+
+    Ok(credited)
+}
+
+
+/// Списывает монеты с баланса. Возвращает `false`, если монет недостаточно
+/// или списание с таким (kind, reference) уже было.
+pub async fn debit(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    amount: Decimal,
+    kind: LedgerKind,
+    reference: &str,
+) -> Result<bool, sqlx::Error> {
+    if amount <= Decimal::ZERO {
+        return Ok(true);
+    }
+
+    let debited = sqlx::query!(
+        r#"
+        UPDATE balances
+        SET balance = balance - $2, updated_at = now()
+        WHERE user_id = $1 AND balance >= $2
+        "#,
+        user_id,
+        amount
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if !debited {
+        return Ok(false);
+    }
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO balance_transactions (id, user_id, amount, kind, reference)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, kind, reference) DO NOTHING
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        -amount,
+        kind.as_str(),
+        reference
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if !inserted {
+        // Повторное списание: возвращаем монеты обратно
+        sqlx::query!(
+            r#"UPDATE balances SET balance = balance + $2 WHERE user_id = $1"#,
+            user_id,
+            amount
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(inserted)
+}
+
+/// Текущий баланс игрока (0, если начислений ещё не было)
+pub async fn get(conn: &mut PgConnection, user_id: Uuid) -> Result<Decimal, sqlx::Error> {
+    let balance = sqlx::query_scalar!(
+        r#"SELECT balance FROM balances WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(balance.unwrap_or(Decimal::ZERO))
+}
+
+/// Расхождение баланса игрока с суммой его операций в журнале
+#[derive(Debug, Clone)]
+pub struct BalanceMismatch {
+    pub user_id: Uuid,
+    pub balance: Decimal,
+    pub ledger: Decimal,
+}
+
+/// Игроки, у которых `balances.balance` не равен сумме `balance_transactions`
+pub async fn find_mismatches(conn: &mut PgConnection) -> Result<Vec<BalanceMismatch>, sqlx::Error> {
+    sqlx::query_as!(
+        BalanceMismatch,
+        r#"
+        SELECT
+            COALESCE(b.user_id, t.user_id) AS "user_id!",
+            COALESCE(b.balance, 0) AS "balance!",
+            COALESCE(t.total, 0) AS "ledger!"
+        FROM balances b
+        FULL OUTER JOIN (
+            SELECT user_id, SUM(amount) AS total
+            FROM balance_transactions
+            GROUP BY user_id
+        ) t ON t.user_id = b.user_id
+        WHERE COALESCE(b.balance, 0) <> COALESCE(t.total, 0)
+        ORDER BY 1
+        "#
+    )
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// Пересчитывает баланс игрока по журналу и возвращает новое значение
+pub async fn reset_to_ledger(conn: &mut PgConnection, user_id: Uuid) -> Result<Decimal, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO balances (user_id, balance)
+        SELECT $1, COALESCE(SUM(amount), 0)
+        FROM balance_transactions
+        WHERE user_id = $1
+        ON CONFLICT (user_id)
+        DO UPDATE SET balance = EXCLUDED.balance, updated_at = now()
+        RETURNING balance
+        "#,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+}
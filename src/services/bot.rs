@@ -0,0 +1,264 @@
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::models::bot::{
+    InlineKeyboardButton, InlineKeyboardMarkup, Message, SendMessage, SuccessfulPayment, Update, WebAppInfo, WebhookReply,
+};
+use crate::services::{payments, referral};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command<'a> {
+    /// /start с необязательным payload из ссылки t.me/bot?start=...
+    Start(Option<&'a str>),
+    Help,
+    Stats,
+    Leaderboard,
+}
+
+/// Разбирает команду вида `/name[@bot] [аргументы]`; неизвестные команды и обычный текст — `None`
+pub fn parse_command(text: &str) -> Option<Command<'_>> {
+    let text = text.trim();
+    let rest = text.strip_prefix('/')?;
+    let (head, args) = match rest.split_once(char::is_whitespace) {
+        Some((head, args)) => (head, Some(args.trim()).filter(|args| !args.is_empty())),
+        None => (rest, None),
+    };
+    let name = head.split('@').next().unwrap_or(head);
+
+    match name.to_ascii_lowercase().as_str() {
+        "start" => Some(Command::Start(args)),
+        "help" => Some(Command::Help),
+        "stats" => Some(Command::Stats),
+        "leaderboard" => Some(Command::Leaderboard),
+        _ => None,
+    }
+}
+
+/// Обрабатывает обновление и возвращает ответ бота, если он нужен
+pub async fn handle_update(pool: &PgPool, config: &Config, update: &Update) -> Result<Option<WebhookReply>, sqlx::Error> {
+    // На pre_checkout_query Telegram ждёт ответа не дольше 10 секунд
+    if let Some(query) = &update.pre_checkout_query {
+        let answer = payments::answer_pre_checkout(pool, query).await?;
+        return Ok(Some(WebhookReply::AnswerPreCheckoutQuery(answer)));
+    }
+    let Some(message) = &update.message else {
+        return Ok(None);
+    };
+    if let (Some(payment), Some(from)) = (&message.successful_payment, &message.from) {
+        return Ok(purchase(pool, config, message, from.id, payment).await?.map(WebhookReply::SendMessage));
+    }
+    let Some(command) = message.text.as_deref().and_then(parse_command) else {
+        return Ok(None);
+    };
+
+    let reply = match command {
+        Command::Start(payload) => start(pool, config, message, payload).await?,
+        Command::Help => reply(message, help_text()),
+        Command::Stats => stats(pool, message).await?,
+        Command::Leaderboard => leaderboard(pool, message).await?,
+    };
+    Ok(Some(WebhookReply::SendMessage(reply)))
+}
+
+async fn purchase(
+    pool: &PgPool,
+    config: &Config,
+    message: &Message,
+    telegram_id: i64,
+    payment: &SuccessfulPayment,
+) -> Result<Option<SendMessage>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let item = payments::fulfil(&mut tx, config, telegram_id, payment).await?;
+    tx.commit().await?;
+
+    Ok(item.map(|item| {
+        reply(
+            message,
+            format!("✅ Purchase confirmed: <b>{}</b>. Thanks for supporting Alien Tap!", escape_html(&item.title)),
+        )
+    }))
+}
+
+fn reply(message: &Message, text: String) -> SendMessage {
+    SendMessage {
+        chat_id: message.chat.id,
+        text,
+        parse_mode: "HTML",
+        reply_markup: None,
+    }
+}
+
+/// Кнопка запуска Mini App; web_app-кнопки разрешены только в личных чатах
+pub fn play_button(config: &Config, startapp: Option<&str>) -> Option<InlineKeyboardMarkup> {
+    let url = config.telegram_webapp_url.as_ref()?;
+    let url = match startapp {
+        Some(param) => format!("{}?startapp={}", url, param),
+        None => url.clone(),
+    };
+
+    // Ссылка t.me/... открывает Mini App через url-кнопку, прямой https-адрес — через web_app
+    let is_direct_link = url.starts_with("https://t.me/");
+    Some(InlineKeyboardMarkup {
+        inline_keyboard: vec![vec![InlineKeyboardButton {
+            text: "🚀 Play".to_string(),
+            web_app: (!is_direct_link).then(|| WebAppInfo { url: url.clone() }),
+            url: is_direct_link.then_some(url),
+        }]],
+    })
+}
+
+async fn start(pool: &PgPool, config: &Config, message: &Message, payload: Option<&str>) -> Result<SendMessage, sqlx::Error> {
+    let referral_payload = payload.filter(|p| referral::parse_start_param(p).is_some());
+
+    // Привязка выполняется при первом входе в Mini App; до этого payload ждёт в pending_referrals
+    if let (Some(payload), Some(from)) = (referral_payload, &message.from) {
+        sqlx::query!(
+            r#"
+            INSERT INTO pending_referrals (telegram_id, start_param)
+            SELECT $1, $2
+            WHERE NOT EXISTS (SELECT 1 FROM users WHERE telegram_id = $1)
+            ON CONFLICT (telegram_id) DO NOTHING
+            "#,
+            from.id,
+            payload
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    // Пользователь начал диалог с ботом: теперь ему можно писать в личку
+    if let (Some(from), "private") = (&message.from, message.chat.kind.as_str()) {
+        sqlx::query!(
+            r#"UPDATE users SET allows_write_to_pm = true WHERE telegram_id = $1"#,
+            from.id
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    let name = message.from.as_ref().and_then(|u| u.first_name.as_deref()).unwrap_or("pilot");
+    let mut reply = reply(
+        message,
+        format!(
+            "👽 Welcome to Alien Tap, {}!\n\nTap the alien, climb the leagues and invite friends to earn more.\n\n{}",
+            escape_html(name),
+            help_text()
+        ),
+    );
+    if message.chat.kind == "private" {
+        reply.reply_markup = play_button(config, referral_payload);
+    }
+    Ok(reply)
+}
+
+fn help_text() -> String {
+    [
+        "<b>Commands</b>",
+        "/start — open the game",
+        "/stats — your score, balance and rank",
+        "/leaderboard — top 10 players",
+        "/help — this message",
+    ]
+    .join("\n")
+}
+
+async fn stats(pool: &PgPool, message: &Message) -> Result<SendMessage, sqlx::Error> {
+    let Some(from) = &message.from else {
+        return Ok(reply(message, "Stats are available only for users.".to_string()));
+    };
+
+    let stats = sqlx::query!(
+        r#"
+        SELECT
+            COALESCE(s.score, 0) AS "score!",
+            COALESCE(b.balance, 0) AS "balance!",
+            (SELECT COUNT(*) + 1 FROM scores o WHERE o.score > COALESCE(s.score, 0)) AS "rank!"
+        FROM users u
+        LEFT JOIN scores s ON s.user_id = u.id
+        LEFT JOIN balances b ON b.user_id = u.id
+        WHERE u.telegram_id = $1
+        "#,
+        from.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let text = match stats {
+        Some(stats) => format!(
+            "📊 <b>Your stats</b>\nScore: {}\nBalance: {}\nRank: #{}",
+            stats.score,
+            format_amount(stats.balance),
+            stats.rank
+        ),
+        None => "You haven't played yet. Send /start and open the game first.".to_string(),
+    };
+    Ok(reply(message, text))
+}
+
+async fn leaderboard(pool: &PgPool, message: &Message) -> Result<SendMessage, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT u.username, u.first_name, COALESCE(s.score, 0) AS "score!"
+        FROM scores s
+        JOIN users u ON s.user_id = u.id
+        ORDER BY s.score DESC NULLS LAST
+        LIMIT 10
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if rows.is_empty() {
+        return Ok(reply(message, "The leaderboard is empty — be the first!".to_string()));
+    }
+
+    let lines: Vec<String> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let name = row.username
+                .map(|username| format!("@{}", username))
+                .or(row.first_name)
+                .unwrap_or_else(|| "Anonymous".to_string());
+            format!("{}. {} — {}", i + 1, escape_html(&name), row.score)
+        })
+        .collect();
+
+    Ok(reply(message, format!("🏆 <b>Top players</b>\n{}", lines.join("\n"))))
+}
+
+fn format_amount(amount: Decimal) -> String {
+    amount.normalize().to_string()
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_with_bot_mention_and_arguments() {
+        assert_eq!(parse_command("/start"), Some(Command::Start(None)));
+        assert_eq!(parse_command("/start ref_ABC123"), Some(Command::Start(Some("ref_ABC123"))));
+        assert_eq!(parse_command("/start@AlienTapBot ref_x"), Some(Command::Start(Some("ref_x"))));
+        assert_eq!(parse_command("  /HELP  "), Some(Command::Help));
+        assert_eq!(parse_command("/stats@AlienTapBot"), Some(Command::Stats));
+        assert_eq!(parse_command("/leaderboard"), Some(Command::Leaderboard));
+    }
+
+    #[test]
+    fn ignores_plain_text_and_unknown_commands() {
+        assert_eq!(parse_command("hello"), None);
+        assert_eq!(parse_command("/unknown"), None);
+        assert_eq!(parse_command(""), None);
+    }
+
+    #[test]
+    fn escapes_html_in_names() {
+        assert_eq!(escape_html("<b>Tom & Jerry</b>"), "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;");
+    }
+}
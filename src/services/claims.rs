@@ -0,0 +1,36 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::models::event::AppEvent;
+use crate::services::events;
+
+/// Записывает переход статуса заявки в историю и рассылает его подписчикам.
+/// Возвращает номер события.
+pub async fn record_status(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    claim_id: Uuid,
+    status: &str,
+) -> Result<i64, sqlx::Error> {
+    let event_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO claim_status_events (claim_id, status)
+        VALUES ($1, $2)
+        RETURNING id
+        "#,
+        claim_id,
+        status
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    events::publish(&mut *conn, &AppEvent::ClaimStatusChanged {
+        user_id,
+        claim_id,
+        event_id,
+        status: status.to_string(),
+    })
+    .await?;
+
+    Ok(event_id)
+}
@@ -0,0 +1,205 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::services::daily;
+
+/// Сезон кланов — календарный месяц в игровом часовом поясе, например "2024-01"
+pub fn current_season(config: &Config) -> String {
+    daily::today(config).format("%Y-%m").to_string()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JoinResult {
+    Joined,
+    ClanNotFound,
+    AlreadyInClan,
+    /// В этом сезоне игрок уже играл за другой клан
+    SeasonLocked,
+    ClanFull,
+}
+
+/// Вступление в клан. Вызывается внутри транзакции: строки игрока и клана
+/// блокируются, чтобы параллельные запросы не обошли лимиты.
+pub async fn join(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    clan_id: Uuid,
+) -> Result<JoinResult, sqlx::Error> {
+    sqlx::query!(r#"SELECT id FROM users WHERE id = $1 FOR UPDATE"#, user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    let clan = sqlx::query!(r#"SELECT id FROM clans WHERE id = $1 FOR UPDATE"#, clan_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if clan.is_none() {
+        return Ok(JoinResult::ClanNotFound);
+    }
+
+    let current = sqlx::query_scalar!(
+        r#"SELECT clan_id FROM clan_members WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if current.is_some() {
+        return Ok(JoinResult::AlreadyInClan);
+    }
+
+    let season = current_season(config);
+    let other_clan_this_season = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM clan_membership_history
+            WHERE user_id = $1 AND season = $2 AND clan_id <> $3
+        ) AS "exists!"
+        "#,
+        user_id,
+        season,
+        clan_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if other_clan_this_season {
+        return Ok(JoinResult::SeasonLocked);
+    }
+
+    let members = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM clan_members WHERE clan_id = $1"#,
+        clan_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if members >= config.clan_max_members {
+        return Ok(JoinResult::ClanFull);
+    }
+
+    sqlx::query!(
+        r#"INSERT INTO clan_members (user_id, clan_id) VALUES ($1, $2)"#,
+        user_id,
+        clan_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO clan_membership_history (id, user_id, clan_id, season)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        clan_id,
+        season
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(JoinResult::Joined)
+}
+
+/// Засчитывает прирост очков клану игрока в текущем сезоне.
+/// Членство, продолжающееся с прошлого сезона, закрывает прошлую запись истории и открывает новую.
+pub async fn add_score(conn: &mut PgConnection, config: &Config, user_id: Uuid, delta: i64) -> Result<(), sqlx::Error> {
+    if delta <= 0 {
+        return Ok(());
+    }
+
+    let season = current_season(config);
+    let credited = sqlx::query!(
+        r#"
+        UPDATE clan_membership_history
+        SET score = score + $3
+        WHERE user_id = $1 AND season = $2 AND left_at IS NULL
+        "#,
+        user_id,
+        season,
+        delta
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if credited > 0 {
+        return Ok(());
+    }
+
+    let Some(clan_id) = sqlx::query_scalar!(
+        r#"SELECT clan_id FROM clan_members WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(());
+    };
+
+    sqlx::query!(
+        r#"UPDATE clan_membership_history SET left_at = now() WHERE user_id = $1 AND left_at IS NULL"#,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO clan_membership_history (id, user_id, clan_id, season, score)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        clan_id,
+        season,
+        delta
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Выход из клана. Если выходит владелец, владельцем становится самый давний участник.
+/// Возвращает `false`, если игрок не состоял в клане.
+pub async fn leave(conn: &mut PgConnection, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let Some(clan_id) = sqlx::query_scalar!(
+        r#"DELETE FROM clan_members WHERE user_id = $1 RETURNING clan_id"#,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(false);
+    };
+
+    sqlx::query!(
+        r#"
+        UPDATE clan_membership_history
+        SET left_at = now()
+        WHERE user_id = $1 AND clan_id = $2 AND left_at IS NULL
+        "#,
+        user_id,
+        clan_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE clans
+        SET owner_id = (
+            SELECT user_id FROM clan_members
+            WHERE clan_id = $1
+            ORDER BY joined_at
+            LIMIT 1
+        )
+        WHERE id = $1 AND owner_id = $2
+        "#,
+        clan_id,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
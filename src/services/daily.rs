@@ -0,0 +1,119 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::achievement::UnlockedAchievement;
+use crate::services::achievements::{self, Metric};
+use crate::services::balance::{self, LedgerKind};
+
+/// Текущий игровой день в часовом поясе из конфигурации
+pub fn today(config: &Config) -> NaiveDate {
+    chrono::Utc::now().with_timezone(&config.daily_timezone).date_naive()
+}
+
+/// Награда за день серии (серия начинается с 1)
+pub fn reward_for_streak(config: &Config, streak: i32) -> Decimal {
+    let index = (streak.max(1) as usize).min(config.daily_rewards.len());
+    config.daily_rewards
+        .get(index.saturating_sub(1))
+        .copied()
+        .unwrap_or(Decimal::ZERO)
+}
+
+#[derive(Debug)]
+pub struct StreakStatus {
+    pub day: NaiveDate,
+    /// Текущая серия: с учётом сегодняшнего дня, если отметка уже есть
+    pub streak: i32,
+    pub checked_in_today: bool,
+}
+
+/// Серия не прерывается, если последняя отметка была сегодня или вчера
+pub async fn status(pool: &PgPool, config: &Config, user_id: Uuid) -> Result<StreakStatus, sqlx::Error> {
+    let day = today(config);
+    let last = sqlx::query!(
+        r#"
+        SELECT day, streak FROM daily_checkins
+        WHERE user_id = $1 AND day >= $2
+        ORDER BY day DESC
+        LIMIT 1
+        "#,
+        user_id,
+        day.pred_opt().unwrap_or(day)
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(StreakStatus {
+        day,
+        streak: last.as_ref().map(|l| l.streak).unwrap_or(0),
+        checked_in_today: last.map(|l| l.day == day).unwrap_or(false),
+    })
+}
+
+#[derive(Debug)]
+pub struct CheckIn {
+    pub day: NaiveDate,
+    pub streak: i32,
+    pub reward: Decimal,
+    pub unlocked_achievements: Vec<UnlockedAchievement>,
+}
+
+/// Отмечает вход за сегодня и начисляет награду.
+/// Возвращает `None`, если игрок уже отмечался сегодня.
+pub async fn check_in(pool: &PgPool, config: &Config, user_id: Uuid) -> Result<Option<CheckIn>, sqlx::Error> {
+    let current = status(pool, config, user_id).await?;
+    if current.checked_in_today {
+        return Ok(None);
+    }
+
+    let streak = current.streak + 1;
+    let reward = reward_for_streak(config, streak);
+
+    let mut tx = pool.begin().await?;
+
+    // Повторная отметка в гонке упирается в первичный ключ (user_id, day)
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO daily_checkins (user_id, day, streak, reward)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id, day) DO NOTHING
+        "#,
+        user_id,
+        current.day,
+        streak,
+        reward
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if !inserted {
+        return Ok(None);
+    }
+
+    balance::credit_earning(
+        &mut tx,
+        config,
+        user_id,
+        reward,
+        LedgerKind::DailyReward,
+        &current.day.to_string(),
+    )
+    .await?;
+
+    let unlocked_achievements =
+        achievements::record(&mut tx, config, user_id, Metric::DailyStreak, streak.into()).await?;
+
+    tx.commit().await?;
+
+    Ok(Some(CheckIn {
+        day: current.day,
+        streak,
+        reward,
+        unlocked_achievements,
+    }))
+}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// Энергия на момент `now` с учётом восстановления; без сохранённого состояния — полный запас
+pub fn current(config: &Config, stored: Option<(i32, DateTime<Utc>)>, now: DateTime<Utc>) -> i32 {
+    let Some((energy, updated_at)) = stored else {
+        return config.energy_max;
+    };
+
+    let elapsed = (now - updated_at).num_seconds().max(0);
+    let restored = elapsed.saturating_mul(config.energy_regen_per_second.max(0).into());
+    (i64::from(energy) + restored).clamp(0, config.energy_max.into()) as i32
+}
+
+/// Секунд до полного восстановления энергии
+pub fn seconds_to_full(config: &Config, energy: i32) -> i64 {
+    let missing = i64::from(config.energy_max - energy).max(0);
+    let regen = i64::from(config.energy_regen_per_second);
+    if missing == 0 || regen <= 0 {
+        return 0;
+    }
+    (missing + regen - 1) / regen
+}
+
+/// Текущая энергия с блокировкой строки до конца транзакции
+pub async fn available(conn: &mut PgConnection, config: &Config, user_id: Uuid) -> Result<i32, sqlx::Error> {
+    let stored = load_for_update(&mut *conn, user_id).await?;
+    Ok(current(config, stored, Utc::now()))
+}
+
+/// Списывает энергию за прирост счёта и возвращает остаток.
+/// Энергия не уходит ниже нуля: счёт принимается, даже если энергии не хватило.
+pub async fn spend(conn: &mut PgConnection, config: &Config, user_id: Uuid, amount: i32) -> Result<i32, sqlx::Error> {
+    let now = Utc::now();
+
+    let stored = load_for_update(&mut *conn, user_id).await?;
+    let energy = (current(config, stored, now) - amount.max(0)).max(0);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO user_energy (user_id, energy, updated_at)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id)
+        DO UPDATE SET energy = EXCLUDED.energy, updated_at = EXCLUDED.updated_at
+        "#,
+        user_id,
+        energy,
+        now
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(energy)
+}
+
+/// Полностью восстанавливает энергию (покупка за Stars)
+pub async fn refill(conn: &mut PgConnection, config: &Config, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO user_energy (user_id, energy, updated_at)
+        VALUES ($1, $2, now())
+        ON CONFLICT (user_id)
+        DO UPDATE SET energy = EXCLUDED.energy, updated_at = EXCLUDED.updated_at
+        "#,
+        user_id,
+        config.energy_max
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn load_for_update(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Option<(i32, DateTime<Utc>)>, sqlx::Error> {
+    let stored = sqlx::query!(
+        r#"SELECT energy, updated_at FROM user_energy WHERE user_id = $1 FOR UPDATE"#,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .map(|row| (row.energy, row.updated_at));
+
+    Ok(stored)
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use sqlx::postgres::PgListener;
+use sqlx::{PgConnection, PgPool};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::event::AppEvent;
+use crate::services::health::{self, Health};
+
+/// Канал NOTIFY, общий для всех инстансов
+const CHANNEL: &str = "alien_tap_events";
+
+/// Сколько событий может отстать медленный подписчик, прежде чем пропустит часть из них
+const SUBSCRIBER_BUFFER: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub enum BusMessage {
+    Event(AppEvent),
+    /// Соединение LISTEN было потеряно и восстановлено: события за это время могли пропасть,
+    /// подписчикам стоит перечитать состояние из базы
+    Resync,
+}
+
+/// Шина событий между инстансами поверх Postgres LISTEN/NOTIFY.
+/// События публикуются в транзакции и доходят до подписчиков только после коммита.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<BusMessage>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(SUBSCRIBER_BUFFER);
+        EventBus { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BusMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Слушает канал и раздаёт события подписчикам этого инстанса, пока сервер не начнёт остановку
+    pub async fn run_listener(self, pool: PgPool, health: Health, shutdown: CancellationToken) {
+        shutdown.run_until_cancelled(self.listen(pool, health.clone())).await;
+        health.down(health::EVENT_LISTENER);
+        tracing::info!("Event bus: listener stopped");
+    }
+
+    /// Цикл LISTEN; при обрыве переподключается
+    async fn listen(self, pool: PgPool, health: Health) {
+        let mut backoff = Duration::from_secs(1);
+        let mut connected_before = false;
+        health.register_connection(health::EVENT_LISTENER);
+
+        loop {
+            let mut listener = match connect(&pool).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::error!("Event bus: failed to listen, retrying in {:?}: {}", backoff, e);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(Duration::from_secs(30));
+                    continue;
+                }
+            };
+
+            backoff = Duration::from_secs(1);
+            health.beat(health::EVENT_LISTENER);
+            if connected_before {
+                tracing::info!("Event bus: reconnected");
+                let _ = self.sender.send(BusMessage::Resync);
+            }
+            connected_before = true;
+
+            loop {
+                // try_recv возвращает None, если соединение оборвалось
+                match listener.try_recv().await {
+                    Ok(Some(notification)) => match serde_json::from_str::<AppEvent>(notification.payload()) {
+                        // Ошибка отправки означает лишь, что подписчиков сейчас нет
+                        Ok(event) => {
+                            let _ = self.sender.send(BusMessage::Event(event));
+                        }
+                        Err(e) => tracing::warn!("Event bus: skipping malformed event: {}", e),
+                    },
+                    Ok(None) => {
+                        tracing::warn!("Event bus: connection lost");
+                        health.down(health::EVENT_LISTENER);
+                        break;
+                    }
+                    Err(e) => {
+                        tracing::error!("Event bus: listener error: {}", e);
+                        health.down(health::EVENT_LISTENER);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn connect(pool: &PgPool) -> Result<PgListener, sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CHANNEL).await?;
+    Ok(listener)
+}
+
+/// Публикует событие; внутри транзакции уйдёт только после коммита
+pub async fn publish(conn: &mut PgConnection, event: &AppEvent) -> Result<(), sqlx::Error> {
+    let payload = serde_json::to_string(event).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+
+    sqlx::query!(r#"SELECT pg_notify($1, $2)"#, CHANNEL, payload)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Имена фоновых задач в ответе /health/ready
+pub const TOURNAMENT_PAYOUTS: &str = "tournament_payouts";
+pub const NOTIFICATION_OUTBOX: &str = "notification_outbox";
+pub const EVENT_LISTENER: &str = "event_listener";
+
+/// Сколько пропущенных циклов периодической задачи терпит readiness
+const MISSED_TICKS: u32 = 3;
+/// Запас на долгий цикл (например, отправка пачки уведомлений с паузами)
+const TICK_SLACK: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy)]
+struct Heartbeat {
+    up: bool,
+    last_beat: Option<Instant>,
+    /// None — задача не тикает периодически, важен только признак up
+    max_silence: Option<Duration>,
+}
+
+/// Состояние фоновой задачи для readiness
+#[derive(Debug, Clone)]
+pub struct WorkerStatus {
+    pub healthy: bool,
+    /// Сколько прошло с последнего пульса; None, если пульса ещё не было
+    pub since_beat: Option<Duration>,
+}
+
+/// Пульс фоновых задач и флаг остановки инстанса; общий для обработчиков и задач
+#[derive(Clone, Default)]
+pub struct Health {
+    draining: Arc<AtomicBool>,
+    workers: Arc<Mutex<BTreeMap<&'static str, Heartbeat>>>,
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Регистрирует периодическую задачу: без пульса дольше нескольких интервалов она считается зависшей
+    pub fn register_periodic(&self, worker: &'static str, interval: Duration) {
+        self.register(worker, Some(interval * MISSED_TICKS + TICK_SLACK));
+    }
+
+    /// Регистрирует задачу, которая сообщает только о том, работает ли она (например, LISTEN)
+    pub fn register_connection(&self, worker: &'static str) {
+        self.register(worker, None);
+    }
+
+    fn register(&self, worker: &'static str, max_silence: Option<Duration>) {
+        self.workers.lock().unwrap().insert(
+            worker,
+            Heartbeat { up: false, last_beat: None, max_silence },
+        );
+    }
+
+    /// Задача жива и выполнила очередной цикл
+    pub fn beat(&self, worker: &'static str) {
+        if let Some(heartbeat) = self.workers.lock().unwrap().get_mut(worker) {
+            heartbeat.up = true;
+            heartbeat.last_beat = Some(Instant::now());
+        }
+    }
+
+    /// Задача временно не работает (например, потеряно соединение)
+    pub fn down(&self, worker: &'static str) {
+        if let Some(heartbeat) = self.workers.lock().unwrap().get_mut(worker) {
+            heartbeat.up = false;
+        }
+    }
+
+    pub fn workers(&self) -> Vec<(&'static str, WorkerStatus)> {
+        let now = Instant::now();
+        self.workers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, heartbeat)| {
+                let since_beat = heartbeat.last_beat.map(|at| now - at);
+                let fresh = match (heartbeat.max_silence, since_beat) {
+                    (Some(max_silence), Some(since_beat)) => since_beat <= max_silence,
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                };
+                (*name, WorkerStatus { healthy: heartbeat.up && fresh, since_beat })
+            })
+            .collect()
+    }
+
+    /// Инстанс останавливается: readiness отвечает 503, чтобы балансировщик снял с него трафик
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn healthy(health: &Health, worker: &str) -> bool {
+        health.workers().into_iter().find(|(name, _)| *name == worker).unwrap().1.healthy
+    }
+
+    #[test]
+    fn periodic_worker_goes_stale_without_beats() {
+        let health = Health::new();
+        health.register(TOURNAMENT_PAYOUTS, Some(Duration::from_millis(20)));
+        assert!(!healthy(&health, TOURNAMENT_PAYOUTS));
+
+        health.beat(TOURNAMENT_PAYOUTS);
+        assert!(healthy(&health, TOURNAMENT_PAYOUTS));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(!healthy(&health, TOURNAMENT_PAYOUTS));
+    }
+
+    #[test]
+    fn connection_worker_follows_up_and_down() {
+        let health = Health::new();
+        health.register_connection(EVENT_LISTENER);
+        assert!(!healthy(&health, EVENT_LISTENER));
+
+        health.beat(EVENT_LISTENER);
+        assert!(healthy(&health, EVENT_LISTENER));
+
+        health.down(EVENT_LISTENER);
+        assert!(!healthy(&health, EVENT_LISTENER));
+    }
+}
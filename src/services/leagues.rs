@@ -0,0 +1,148 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::league::{League, LeagueStatus};
+use crate::services::balance::{self, LedgerKind};
+
+/// Индекс лиги для количества очков
+pub fn league_index(config: &Config, score: i32) -> usize {
+    config.leagues
+        .iter()
+        .rposition(|league| score >= league.min_score)
+        .unwrap_or(0)
+}
+
+pub fn find_league<'a>(config: &'a Config, name: &str) -> Option<(usize, &'a League)> {
+    config.leagues
+        .iter()
+        .enumerate()
+        .find(|(_, league)| league.name.eq_ignore_ascii_case(name))
+}
+
+/// Диапазон очков лиги: [min_score, max_score), max_score = None для верхней лиги
+pub fn score_range(config: &Config, index: usize) -> (i32, Option<i32>) {
+    let min = config.leagues[index].min_score;
+    let max = config.leagues.get(index + 1).map(|next| next.min_score);
+    (min, max)
+}
+
+/// Приводит сохранённую лигу игрока в соответствие с очками.
+/// За каждую впервые достигнутую лигу один раз начисляется награда.
+pub async fn sync(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    score: i32,
+) -> Result<LeagueStatus, sqlx::Error> {
+    let index = league_index(config, score);
+    let league = &config.leagues[index];
+
+    let stored = sqlx::query!(
+        r#"
+        SELECT league, previous_league, changed_at FROM user_leagues
+        WHERE user_id = $1
+        FOR UPDATE
+        "#,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let old_index = stored
+        .as_ref()
+        .map(|s| find_league(config, &s.league).map(|(i, _)| i).unwrap_or(0))
+        .unwrap_or(0);
+
+    let (previous_league, changed_at, change) = match stored {
+        Some(stored) if stored.league == league.name => (stored.previous_league, Some(stored.changed_at), None),
+        stored => {
+            let previous = stored.map(|s| s.league);
+            let row = sqlx::query!(
+                r#"
+                INSERT INTO user_leagues (user_id, league, previous_league, changed_at)
+                VALUES ($1, $2, $3, now())
+                ON CONFLICT (user_id)
+                DO UPDATE SET
+                    league = EXCLUDED.league,
+                    previous_league = EXCLUDED.previous_league,
+                    changed_at = EXCLUDED.changed_at
+                RETURNING changed_at
+                "#,
+                user_id,
+                league.name,
+                previous
+            )
+            .fetch_one(&mut *conn)
+            .await?;
+
+            let change = match previous {
+                Some(_) if index > old_index => Some("promoted".to_string()),
+                Some(_) if index < old_index => Some("demoted".to_string()),
+                None if index > 0 => Some("promoted".to_string()),
+                _ => None,
+            };
+            (previous, Some(row.changed_at), change)
+        }
+    };
+
+    let mut promotion_reward = Decimal::ZERO;
+    if index > old_index {
+        for promoted in &config.leagues[old_index + 1..=index] {
+            promotion_reward += grant_promotion(&mut *conn, config, user_id, promoted).await?;
+        }
+    }
+
+    let next = config.leagues.get(index + 1);
+
+    Ok(LeagueStatus {
+        league: league.name.clone(),
+        previous_league,
+        change,
+        changed_at,
+        next_league: next.map(|l| l.name.clone()),
+        next_league_score: next.map(|l| l.min_score),
+        promotion_reward,
+    })
+}
+
+async fn grant_promotion(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    league: &League,
+) -> Result<Decimal, sqlx::Error> {
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO league_promotions (user_id, league, reward)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, league) DO NOTHING
+        "#,
+        user_id,
+        league.name,
+        league.reward
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if !inserted {
+        return Ok(Decimal::ZERO);
+    }
+
+    balance::credit_earning(
+        &mut *conn,
+        config,
+        user_id,
+        league.reward,
+        LedgerKind::LeaguePromotion,
+        &league.name,
+    )
+    .await?;
+
+    tracing::info!("League promotion: user_id={}, league={}", user_id, league.name);
+
+    Ok(league.reward)
+}
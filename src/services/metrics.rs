@@ -0,0 +1,90 @@
+//! Метрики в формате Prometheus. Значения пишутся через фасад `metrics` и отдаются
+//! на отдельном порту (`METRICS_PORT`), недоступном снаружи.
+
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::PgPool;
+
+use crate::utils::errors::AppError;
+
+/// Границы гистограмм длительности запросов и выплат, секунды
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+/// Задержка выплаты после окончания турнира: от секунд до десятков минут
+const PAYOUT_LAG_BUCKETS: &[f64] = &[1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0];
+
+/// Устанавливает глобальный recorder; без него все вызовы ниже ничего не делают (например, в тестах)
+pub fn install() -> Result<PrometheusHandle, BuildError> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full("tournament_payout_lag_seconds".to_string()), PAYOUT_LAG_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)?
+        .install_recorder()
+}
+
+/// Middleware: число и длительность запросов по шаблону маршрута (`/claim/:id/events`), а не по URI,
+/// чтобы идентификаторы не раздували число рядов
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::counter!("http_requests_total", "method" => method.clone(), "route" => route.clone(), "status" => status)
+        .increment(1);
+    metrics::histogram!("http_request_duration_seconds", "method" => method, "route" => route)
+        .record(started.elapsed().as_secs_f64());
+
+    response
+}
+
+/// Заполненность пула соединений; снимается при каждом опросе /metrics
+pub fn record_pool(pool: &PgPool) {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+    metrics::gauge!("db_pool_connections", "state" => "in_use").set(f64::from(size.saturating_sub(idle)));
+    metrics::gauge!("db_pool_connections", "state" => "idle").set(f64::from(idle));
+    metrics::gauge!("db_pool_max_connections").set(f64::from(pool.options().get_max_connections()));
+}
+
+/// Результат входа через Telegram; причина отказа выводится из типа ошибки
+pub fn record_auth(result: Result<(), &AppError>) {
+    let (result, reason) = match result {
+        Ok(()) => ("success", "none"),
+        Err(AppError::Authentication(_)) => ("failure", "invalid_signature"),
+        Err(AppError::Validation(_)) => ("failure", "invalid_payload"),
+        Err(AppError::Unauthorized) => ("failure", "unauthorized"),
+        Err(AppError::NotFound(_)) => ("failure", "not_found"),
+        Err(AppError::Database(_)) | Err(AppError::Internal(_)) => ("failure", "internal"),
+    };
+    metrics::counter!("auth_attempts_total", "result" => result, "reason" => reason).increment(1);
+}
+
+/// Тапы после коммита: сколько очков засчитано и сколько отброшено (нет энергии, устаревший счёт)
+pub fn record_taps(source: &'static str, requested: i32, accepted: i32) {
+    let accepted = accepted.max(0);
+    let rejected = (requested - accepted).max(0);
+    metrics::counter!("taps_total", "source" => source, "result" => "accepted").increment(accepted as u64);
+    metrics::counter!("taps_total", "source" => source, "result" => "rejected").increment(rejected as u64);
+}
+
+/// Переход заявки на вывод в статус
+pub fn record_claim(status: &'static str) {
+    metrics::counter!("claims_total", "status" => status).increment(1);
+}
+
+/// Выплата турнира: сколько длилась транзакция и насколько позже окончания турнира она прошла
+pub fn record_payout(duration: Duration, lag: Duration) {
+    metrics::histogram!("tournament_payout_duration_seconds").record(duration.as_secs_f64());
+    metrics::histogram!("tournament_payout_lag_seconds").record(lag.as_secs_f64());
+}
@@ -0,0 +1,19 @@
+pub mod achievements;
+pub mod balance;
+pub mod bot;
+pub mod claims;
+pub mod clans;
+pub mod daily;
+pub mod energy;
+pub mod events;
+pub mod health;
+pub mod leagues;
+pub mod metrics;
+pub mod notifications;
+pub mod passive;
+pub mod payments;
+pub mod realtime;
+pub mod referral;
+pub mod score;
+pub mod tasks;
+pub mod tournaments;
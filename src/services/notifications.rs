@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::bot::{InlineKeyboardMarkup, SendMessage};
+use crate::services::health::{self, Health};
+use crate::services::{bot, daily};
+use crate::utils::bot_api::{BotApi, BotApiError};
+
+/// Сколько уведомлений забирается из outbox за один проход
+const BATCH_SIZE: i64 = 50;
+
+/// На сколько забранное уведомление скрыто от других инстансов
+const LEASE_SECS: f64 = 60.0;
+
+pub struct Notification {
+    pub kind: &'static str,
+    /// Уникален в пределах (user_id, kind): повторная постановка не создаёт дубль
+    pub dedupe_key: String,
+    pub text: String,
+}
+
+pub fn claim_completed(claim_id: Uuid, amount: rust_decimal::Decimal) -> Notification {
+    Notification {
+        kind: "claim_completed",
+        dedupe_key: claim_id.to_string(),
+        text: format!("✅ Your withdrawal of {} tokens is complete.", amount.normalize()),
+    }
+}
+
+/// Ставит уведомление в outbox в транзакции вызывающего: отправится только после коммита
+pub async fn enqueue(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    notification: Notification,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications (user_id, kind, dedupe_key, text, reply_markup)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, kind, dedupe_key) DO NOTHING
+        "#,
+        user_id,
+        notification.kind,
+        notification.dedupe_key,
+        notification.text,
+        play_button_json(config)
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+fn play_button_json(config: &Config) -> Option<serde_json::Value> {
+    bot::play_button(config, None).and_then(|markup| serde_json::to_value(markup).ok())
+}
+
+/// Уведомляет игроков, которых `user_id` обогнал, подняв счёт с `previous` до `score`.
+/// Каждому игроку — не больше одного такого уведомления в день.
+pub async fn enqueue_overtaken(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    previous: i32,
+    score: i32,
+) -> Result<(), sqlx::Error> {
+    if score <= previous {
+        return Ok(());
+    }
+
+    let name = sqlx::query_scalar!(
+        r#"SELECT COALESCE(username, first_name, 'Someone') AS "name!" FROM users WHERE id = $1"#,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications (user_id, kind, dedupe_key, text, reply_markup)
+        SELECT s.user_id, 'overtaken', $4, $5, $6
+        FROM scores s
+        JOIN users u ON u.id = s.user_id
+        WHERE s.score >= $2 AND s.score < $3
+            AND s.user_id <> $1
+            AND u.allows_write_to_pm
+        ORDER BY s.score DESC
+        LIMIT 10
+        ON CONFLICT (user_id, kind, dedupe_key) DO NOTHING
+        "#,
+        user_id,
+        previous,
+        score,
+        daily::today(config).to_string(),
+        format!(
+            "⚠️ {} just overtook you on the leaderboard! Tap back to reclaim your spot.",
+            bot::escape_html(&name)
+        ),
+        play_button_json(config)
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Уведомления, зависящие от времени: энергия восстановилась, ежедневная награда доступна.
+/// Ключи дедупликации делают проход идемпотентным, его можно запускать на всех инстансах.
+async fn schedule(pool: &PgPool, config: &Config) -> Result<(), sqlx::Error> {
+    if config.energy_regen_per_second > 0 {
+        // Ключ — момент последней траты энергии: одно уведомление на каждое опустошение
+        sqlx::query!(
+            r#"
+            INSERT INTO notifications (user_id, kind, dedupe_key, text, reply_markup)
+            SELECT e.user_id, 'energy_full', e.updated_at::TEXT, $3, $4
+            FROM user_energy e
+            JOIN users u ON u.id = e.user_id
+            WHERE u.allows_write_to_pm
+                AND e.energy < $1
+                AND e.updated_at > now() - interval '1 day'
+                AND e.updated_at + make_interval(secs => ($1 - e.energy)::FLOAT8 / $2) <= now()
+            ON CONFLICT (user_id, kind, dedupe_key) DO NOTHING
+            "#,
+            config.energy_max,
+            f64::from(config.energy_regen_per_second),
+            "⚡ Your energy is full — time to tap!",
+            play_button_json(config)
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    // Напоминаем тем, кто отметился вчера и ещё не отметился сегодня
+    let today = daily::today(config);
+    sqlx::query!(
+        r#"
+        INSERT INTO notifications (user_id, kind, dedupe_key, text, reply_markup)
+        SELECT d.user_id, 'daily_reward', $1::DATE::TEXT,
+            format($3, d.streak + 1), $4
+        FROM daily_checkins d
+        JOIN users u ON u.id = d.user_id
+        WHERE d.day = $2
+            AND u.allows_write_to_pm
+            AND NOT EXISTS (
+                SELECT 1 FROM daily_checkins t WHERE t.user_id = d.user_id AND t.day = $1
+            )
+        ON CONFLICT (user_id, kind, dedupe_key) DO NOTHING
+        "#,
+        today,
+        today.pred_opt().unwrap_or(today),
+        "🎁 Your daily reward is ready! Check in to reach a %s-day streak.",
+        play_button_json(config)
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Ограничения Bot API: не чаще раза в `chat_interval` в один чат и не больше `per_second` в целом
+struct RateLimiter {
+    chat_interval: Duration,
+    send_interval: Duration,
+    last_sent: HashMap<i64, Instant>,
+    paused_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    fn new(config: &Config) -> Self {
+        RateLimiter {
+            chat_interval: Duration::from_millis(config.notification_chat_interval_ms),
+            send_interval: Duration::from_secs(1) / config.notification_rate_per_second.max(1),
+            last_sent: HashMap::new(),
+            paused_until: None,
+        }
+    }
+
+    /// Сколько ещё ждать до отправки в этот чат
+    fn chat_wait(&self, chat_id: i64) -> Option<Duration> {
+        let elapsed = self.last_sent.get(&chat_id)?.elapsed();
+        self.chat_interval.checked_sub(elapsed).filter(|wait| !wait.is_zero())
+    }
+
+    fn sent(&mut self, chat_id: i64) {
+        let now = Instant::now();
+        self.last_sent.insert(chat_id, now);
+        self.last_sent.retain(|_, at| now - *at < self.chat_interval);
+    }
+}
+
+struct Leased {
+    id: i64,
+    user_id: Uuid,
+    telegram_id: i64,
+    allows_write_to_pm: bool,
+    text: String,
+    reply_markup: Option<serde_json::Value>,
+    attempts: i32,
+}
+
+/// Фоновая задача: отправляет уведомления из outbox.
+/// При остановке сервера прекращает отправку между сообщениями; неотправленные остаются в outbox.
+pub async fn run_outbox_worker(pool: PgPool, config: Config, bot_api: BotApi, health: Health, shutdown: CancellationToken) {
+    let period = Duration::from_secs(config.notification_poll_interval_secs.max(1));
+    let mut interval = tokio::time::interval(period);
+    let mut limiter = RateLimiter::new(&config);
+    health.register_periodic(health::NOTIFICATION_OUTBOX, period);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+        // Пауза после 429 — штатное ожидание, задача при этом жива
+        health.beat(health::NOTIFICATION_OUTBOX);
+
+        if let Some(until) = limiter.paused_until {
+            if Instant::now() < until {
+                continue;
+            }
+            limiter.paused_until = None;
+        }
+
+        if let Err(e) = schedule(&pool, &config).await {
+            tracing::error!("Failed to schedule notifications: {}", e);
+        }
+        if let Err(e) = deliver_batch(&pool, &config, &bot_api, &mut limiter, &shutdown).await {
+            tracing::error!("Failed to deliver notifications: {}", e);
+        }
+    }
+
+    tracing::info!("Notification outbox worker stopped");
+}
+
+async fn deliver_batch(
+    pool: &PgPool,
+    config: &Config,
+    bot_api: &BotApi,
+    limiter: &mut RateLimiter,
+    shutdown: &CancellationToken,
+) -> Result<(), sqlx::Error> {
+    // Аренда вместо долгой транзакции: другие инстансы пропускают забранные строки
+    let batch = sqlx::query_as!(
+        Leased,
+        r#"
+        WITH leased AS (
+            UPDATE notifications
+            SET next_attempt_at = now() + make_interval(secs => $2)
+            WHERE id IN (
+                SELECT id FROM notifications
+                WHERE status = 'pending' AND next_attempt_at <= now()
+                ORDER BY next_attempt_at, id
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, user_id, text, reply_markup, attempts
+        )
+        SELECT l.id, l.user_id, u.telegram_id, u.allows_write_to_pm, l.text, l.reply_markup, l.attempts
+        FROM leased l
+        JOIN users u ON u.id = l.user_id
+        ORDER BY l.id
+        "#,
+        BATCH_SIZE,
+        LEASE_SECS
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut batch = batch.into_iter();
+    while let Some(notification) = batch.next() {
+        // Остаток пачки вернётся в работу после истечения аренды
+        if shutdown.is_cancelled() {
+            break;
+        }
+        if !notification.allows_write_to_pm {
+            mark(pool, notification.id, "skipped", Some("User does not allow messages from the bot")).await?;
+            continue;
+        }
+
+        if let Some(wait) = limiter.chat_wait(notification.telegram_id) {
+            reschedule(pool, notification.id, wait, notification.attempts, None).await?;
+            continue;
+        }
+
+        let message = SendMessage {
+            chat_id: notification.telegram_id,
+            text: notification.text.clone(),
+            parse_mode: "HTML",
+            reply_markup: notification
+                .reply_markup
+                .clone()
+                .and_then(|markup| serde_json::from_value::<InlineKeyboardMarkup>(markup).ok()),
+        };
+
+        match bot_api.send_message(&message).await {
+            Ok(_) => {
+                limiter.sent(notification.telegram_id);
+                mark(pool, notification.id, "sent", None).await?;
+            }
+            Err(BotApiError::Api { code: 429, retry_after, description }) => {
+                // Flood control: откладываем эту и оставшиеся отправки, попытка не засчитывается
+                let wait = Duration::from_secs(retry_after.unwrap_or(1));
+                tracing::warn!("Bot API rate limited for {:?}: {}", wait, description);
+                limiter.paused_until = Some(Instant::now() + wait);
+                reschedule(pool, notification.id, wait, notification.attempts, Some(&description)).await?;
+                for rest in batch.by_ref() {
+                    reschedule(pool, rest.id, wait, rest.attempts, None).await?;
+                }
+                break;
+            }
+            Err(BotApiError::Api { code: 403, description, .. }) => {
+                // Бот заблокирован или чат недоступен: больше не пишем этому игроку
+                sqlx::query!(
+                    r#"UPDATE users SET allows_write_to_pm = false WHERE id = $1"#,
+                    notification.user_id
+                )
+                .execute(pool)
+                .await?;
+                mark(pool, notification.id, "failed", Some(&description)).await?;
+            }
+            Err(BotApiError::Api { code: 400, description, .. }) => {
+                mark(pool, notification.id, "failed", Some(&description)).await?;
+            }
+            Err(e) => {
+                tracing::warn!("sendMessage failed for notification {}: {}", notification.id, e);
+                let attempts = notification.attempts + 1;
+                if attempts >= config.notification_max_attempts {
+                    mark(pool, notification.id, "failed", Some(&e.summary())).await?;
+                } else {
+                    // Экспоненциальная задержка: 30 с, 1 мин, 2 мин, ...
+                    let wait = Duration::from_secs(30 * 2u64.pow(attempts.clamp(1, 10) as u32 - 1));
+                    reschedule(pool, notification.id, wait, attempts, Some(&e.summary())).await?;
+                }
+            }
+        }
+
+        tokio::time::sleep(limiter.send_interval).await;
+    }
+
+    Ok(())
+}
+
+async fn mark(pool: &PgPool, id: i64, status: &str, error: Option<&str>) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE notifications
+        SET status = $2,
+            last_error = COALESCE($3, last_error),
+            sent_at = CASE WHEN $2 = 'sent' THEN now() ELSE sent_at END
+        WHERE id = $1
+        "#,
+        id,
+        status,
+        error
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn reschedule(pool: &PgPool, id: i64, wait: Duration, attempts: i32, error: Option<&str>) -> Result<(), sqlx::Error> {
+    let next_attempt_at = Utc::now() + chrono::Duration::from_std(wait).unwrap_or_default();
+    sqlx::query!(
+        r#"
+        UPDATE notifications
+        SET next_attempt_at = $2, attempts = $3, last_error = COALESCE($4, last_error)
+        WHERE id = $1
+        "#,
+        id,
+        next_attempt_at,
+        attempts,
+        error
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(chat_interval_ms: &str, rate_per_second: &str) -> RateLimiter {
+        let config = Config::from_vars(|key| match key {
+            "DATABASE_URL" => Ok("postgres://localhost/unused".to_string()),
+            "TELEGRAM_BOT_TOKEN" => Ok("123:test".to_string()),
+            "JWT_SECRET" => Ok("test".to_string()),
+            "NOTIFICATION_CHAT_INTERVAL_MS" => Ok(chat_interval_ms.to_string()),
+            "NOTIFICATION_RATE_PER_SECOND" => Ok(rate_per_second.to_string()),
+            _ => Err(std::env::VarError::NotPresent),
+        })
+        .unwrap();
+        RateLimiter::new(&config)
+    }
+
+    #[test]
+    fn global_rate_spaces_sends_evenly() {
+        assert_eq!(limiter("1000", "25").send_interval, Duration::from_millis(40));
+        // Нулевой лимит не превращается в деление на ноль
+        assert_eq!(limiter("1000", "0").send_interval, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn chat_waits_out_its_interval_independently_of_others() {
+        let mut limiter = limiter("50", "25");
+        assert_eq!(limiter.chat_wait(1), None);
+
+        limiter.sent(1);
+        let wait = limiter.chat_wait(1).unwrap();
+        assert!(wait <= Duration::from_millis(50));
+        assert_eq!(limiter.chat_wait(2), None);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(limiter.chat_wait(1), None);
+
+        // Истёкшие записи вычищаются при следующей отправке
+        limiter.sent(2);
+        assert!(!limiter.last_sent.contains_key(&1));
+        assert!(limiter.chat_wait(2).is_some());
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::services::balance::{self, LedgerKind};
+
+#[derive(Debug)]
+pub struct Accrual {
+    pub level: i32,
+    /// Сколько начислено за время отсутствия в этом запросе
+    pub earned: Decimal,
+}
+
+/// Доход в час для уровня
+pub fn income_per_hour(config: &Config, level: i32) -> Decimal {
+    config.passive_income_per_hour
+        .get(level.max(0) as usize)
+        .copied()
+        .unwrap_or(Decimal::ZERO)
+}
+
+/// Стоимость улучшения с уровня `level` на следующий; `None`, если уровень максимальный
+pub fn upgrade_cost(config: &Config, level: i32) -> Option<Decimal> {
+    let next = level.max(0) as usize + 1;
+    if next >= config.passive_income_per_hour.len() {
+        return None;
+    }
+    config.passive_upgrade_costs.get(level.max(0) as usize).copied()
+}
+
+/// Лениво начисляет пассивный доход с момента последнего начисления,
+/// но не больше чем за `passive_income_max_hours` часов.
+///
+/// Время, за которое накопилось меньше 0.01, переносится на следующий запрос.
+pub async fn accrue(conn: &mut PgConnection, config: &Config, user_id: Uuid) -> Result<Accrual, sqlx::Error> {
+    let now = Utc::now();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO passive_income (user_id, last_accrued_at)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO NOTHING
+        "#,
+        user_id,
+        now
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let state = sqlx::query!(
+        r#"
+        SELECT level, last_accrued_at FROM passive_income
+        WHERE user_id = $1
+        FOR UPDATE
+        "#,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let earned = earned_between(config, state.level, state.last_accrued_at, now);
+    if earned.is_zero() {
+        return Ok(Accrual {
+            level: state.level,
+            earned,
+        });
+    }
+
+    sqlx::query!(
+        r#"UPDATE passive_income SET last_accrued_at = $2 WHERE user_id = $1"#,
+        user_id,
+        now
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    balance::credit_earning(
+        &mut *conn,
+        config,
+        user_id,
+        earned,
+        LedgerKind::PassiveIncome,
+        &state.last_accrued_at.to_rfc3339(),
+    )
+    .await?;
+
+    Ok(Accrual {
+        level: state.level,
+        earned,
+    })
+}
+
+fn earned_between(config: &Config, level: i32, from: DateTime<Utc>, to: DateTime<Utc>) -> Decimal {
+    let max_seconds = config.passive_income_max_hours.max(0) * 3600;
+    let seconds = (to - from).num_seconds().clamp(0, max_seconds);
+
+    (income_per_hour(config, level) * Decimal::from(seconds) / Decimal::from(3600)).trunc_with_scale(2)
+}
+
+/// Повышает уровень пассивного дохода за монеты.
+/// Перед повышением начисляет доход по старому уровню.
+pub async fn upgrade(conn: &mut PgConnection, config: &Config, user_id: Uuid) -> Result<UpgradeResult, sqlx::Error> {
+    let accrual = accrue(&mut *conn, config, user_id).await?;
+
+    let Some(cost) = upgrade_cost(config, accrual.level) else {
+        return Ok(UpgradeResult::MaxLevel);
+    };
+
+    let next_level = accrual.level + 1;
+    if !balance::debit(&mut *conn, user_id, cost, LedgerKind::PassiveUpgrade, &next_level.to_string()).await? {
+        return Ok(UpgradeResult::InsufficientBalance);
+    }
+
+    sqlx::query!(
+        r#"UPDATE passive_income SET level = $2 WHERE user_id = $1"#,
+        user_id,
+        next_level
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(UpgradeResult::Upgraded { level: next_level, cost })
+}
+
+#[derive(Debug)]
+pub enum UpgradeResult {
+    Upgraded { level: i32, cost: Decimal },
+    MaxLevel,
+    InsufficientBalance,
+}
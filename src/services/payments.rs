@@ -0,0 +1,306 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::bot::{AnswerPreCheckoutQuery, PreCheckoutQuery, SuccessfulPayment};
+use crate::models::shop::ShopItem;
+use crate::services::balance::{self, LedgerKind};
+use crate::services::energy;
+use crate::utils::bot_api::{BotApi, BotApiError};
+
+/// Валюта Telegram Stars
+pub const STARS_CURRENCY: &str = "XTR";
+
+/// Заказ, которому соответствует payload инвойса
+#[derive(Debug)]
+pub struct PendingOrder {
+    pub status: String,
+    pub telegram_id: i64,
+    pub price_stars: i32,
+    pub item_active: bool,
+}
+
+pub enum RefundResult {
+    Refunded { reverted: bool },
+    NotFound,
+    NotPaid,
+}
+
+/// Проверяет заказ перед оплатой; `Err` — текст, который Telegram покажет покупателю
+pub fn validate_pre_checkout(order: Option<&PendingOrder>, query: &PreCheckoutQuery) -> Result<(), &'static str> {
+    let order = order.ok_or("Order not found")?;
+    if order.telegram_id != query.from.id {
+        return Err("This order belongs to another user");
+    }
+    if order.status != "created" {
+        return Err("This order has already been paid");
+    }
+    if !order.item_active {
+        return Err("This item is no longer available");
+    }
+    if query.currency != STARS_CURRENCY || query.total_amount != i64::from(order.price_stars) {
+        return Err("The price has changed, please try again");
+    }
+    Ok(())
+}
+
+pub async fn create_order(conn: &mut PgConnection, user_id: Uuid, item_id: &str) -> Result<Option<(Uuid, ShopItem)>, sqlx::Error> {
+    let item = sqlx::query_as!(
+        ShopItem,
+        r#"
+        SELECT id, kind, title, description, price_stars, amount
+        FROM shop_items
+        WHERE id = $1 AND is_active
+        "#,
+        item_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(item) = item else {
+        return Ok(None);
+    };
+
+    let order_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        INSERT INTO star_orders (id, user_id, item_id, price_stars)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        order_id,
+        user_id,
+        item.id,
+        item.price_stars
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(Some((order_id, item)))
+}
+
+async fn load_pending_order(pool: &PgPool, payload: &str) -> Result<Option<PendingOrder>, sqlx::Error> {
+    let Ok(order_id) = Uuid::parse_str(payload) else {
+        return Ok(None);
+    };
+
+    sqlx::query_as!(
+        PendingOrder,
+        r#"
+        SELECT o.status, u.telegram_id, o.price_stars, i.is_active AS item_active
+        FROM star_orders o
+        JOIN users u ON u.id = o.user_id
+        JOIN shop_items i ON i.id = o.item_id
+        WHERE o.id = $1
+        "#,
+        order_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn answer_pre_checkout(pool: &PgPool, query: &PreCheckoutQuery) -> Result<AnswerPreCheckoutQuery, sqlx::Error> {
+    let order = load_pending_order(pool, &query.invoice_payload).await?;
+    let result = validate_pre_checkout(order.as_ref(), query);
+
+    if let Err(reason) = result {
+        tracing::warn!("Pre-checkout rejected: payload={}, reason={}", query.invoice_payload, reason);
+    }
+
+    Ok(AnswerPreCheckoutQuery {
+        pre_checkout_query_id: query.id.clone(),
+        ok: result.is_ok(),
+        error_message: result.err().map(str::to_string),
+    })
+}
+
+/// Выдаёт покупку после successful_payment. Повторная доставка того же платежа ничего не меняет:
+/// возвращает `None`, если заказ уже оплачен, не найден или оплачен не той суммой.
+/// Проверки pre-checkout повторяются: платёж мог прийти и без неё
+pub async fn fulfil(
+    conn: &mut PgConnection,
+    config: &Config,
+    telegram_id: i64,
+    payment: &SuccessfulPayment,
+) -> Result<Option<ShopItem>, sqlx::Error> {
+    let Ok(order_id) = Uuid::parse_str(&payment.invoice_payload) else {
+        tracing::error!("Payment with unknown payload: {}", payment.invoice_payload);
+        return Ok(None);
+    };
+
+    let order = sqlx::query!(
+        r#"
+        UPDATE star_orders o
+        SET status = 'paid', telegram_payment_charge_id = $2, paid_at = now()
+        FROM users u
+        WHERE o.id = $1 AND o.status = 'created'
+            AND u.id = o.user_id AND u.telegram_id = $3
+            AND o.price_stars::BIGINT = $4 AND $5::TEXT = $6::TEXT
+        RETURNING o.user_id, o.item_id
+        "#,
+        order_id,
+        payment.telegram_payment_charge_id,
+        telegram_id,
+        payment.total_amount,
+        payment.currency,
+        STARS_CURRENCY
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(order) = order else {
+        // Повторная доставка уже выданного платежа — норма; в остальных случаях звёзды списаны,
+        // а покупка не выдана, и заказ нужно разбирать вручную (например, вернуть звёзды)
+        let duplicate = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM star_orders WHERE id = $1 AND telegram_payment_charge_id = $2) AS "exists!""#,
+            order_id,
+            payment.telegram_payment_charge_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        if duplicate {
+            tracing::info!("Payment already fulfilled: order_id={}", order_id);
+        } else {
+            tracing::error!(
+                "Paid charge matches no order: order_id={}, telegram_id={}, paid={} {}, charge_id={}",
+                order_id,
+                telegram_id,
+                payment.total_amount,
+                payment.currency,
+                payment.telegram_payment_charge_id
+            );
+        }
+        return Ok(None);
+    };
+
+    let item = sqlx::query_as!(
+        ShopItem,
+        r#"
+        SELECT id, kind, title, description, price_stars, amount
+        FROM shop_items
+        WHERE id = $1
+        "#,
+        order.item_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    match item.kind.as_str() {
+        "balance" => {
+            balance::credit(&mut *conn, order.user_id, item.amount, LedgerKind::StarsPurchase, &order_id.to_string()).await?;
+        }
+        "energy" => energy::refill(&mut *conn, config, order.user_id).await?,
+        kind => tracing::error!("Unknown shop item kind {} for order {}", kind, order_id),
+    }
+
+    tracing::info!(
+        "Stars order fulfilled: order_id={}, item_id={}, paid={} {}",
+        order_id,
+        item.id,
+        payment.total_amount,
+        payment.currency
+    );
+
+    Ok(Some(item))
+}
+
+/// Возвращает звёзды через refundStarPayment и списывает начисленные монеты, если они ещё есть
+pub async fn refund(pool: &PgPool, bot_api: &BotApi, order_id: Uuid) -> Result<RefundResult, anyhow::Error> {
+    let order = sqlx::query!(
+        r#"
+        SELECT o.user_id, o.status, o.telegram_payment_charge_id, u.telegram_id, i.kind, i.amount
+        FROM star_orders o
+        JOIN users u ON u.id = o.user_id
+        JOIN shop_items i ON i.id = o.item_id
+        WHERE o.id = $1
+        "#,
+        order_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    let Some(order) = order else {
+        return Ok(RefundResult::NotFound);
+    };
+    let (Some(charge_id), "paid") = (order.telegram_payment_charge_id, order.status.as_str()) else {
+        return Ok(RefundResult::NotPaid);
+    };
+
+    match bot_api.refund_star_payment(order.telegram_id, &charge_id).await {
+        Ok(_) => {}
+        // Telegram уже вернул звёзды — фиксируем возврат у себя
+        Err(BotApiError::Api { description, .. }) if description.contains("CHARGE_ALREADY_REFUNDED") => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    // Из двух параллельных возвратов списание делает только тот, кто первым сменил статус
+    let mut tx = pool.begin().await?;
+    let updated = sqlx::query!(
+        r#"UPDATE star_orders SET status = 'refunded', refunded_at = now() WHERE id = $1 AND status = 'paid'"#,
+        order_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Ok(RefundResult::NotPaid);
+    }
+
+    let reverted = match order.kind.as_str() {
+        "balance" => {
+            balance::debit(&mut tx, order.user_id, order.amount, LedgerKind::StarsRefund, &order_id.to_string()).await?
+        }
+        _ => false,
+    };
+    tx.commit().await?;
+
+    tracing::info!("Stars order refunded: order_id={}, reverted={}", order_id, reverted);
+
+    Ok(RefundResult::Refunded { reverted })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::TelegramUser;
+
+    fn query(from: i64, currency: &str, total_amount: i64) -> PreCheckoutQuery {
+        PreCheckoutQuery {
+            id: "query".to_string(),
+            from: TelegramUser {
+                id: from,
+                username: None,
+                first_name: None,
+                last_name: None,
+                allows_write_to_pm: false,
+            },
+            currency: currency.to_string(),
+            total_amount,
+            invoice_payload: Uuid::nil().to_string(),
+        }
+    }
+
+    fn order(status: &str) -> PendingOrder {
+        PendingOrder {
+            status: status.to_string(),
+            telegram_id: 42,
+            price_stars: 50,
+            item_active: true,
+        }
+    }
+
+    #[test]
+    fn accepts_matching_order() {
+        assert_eq!(validate_pre_checkout(Some(&order("created")), &query(42, "XTR", 50)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_mismatched_orders() {
+        assert!(validate_pre_checkout(None, &query(42, "XTR", 50)).is_err());
+        assert!(validate_pre_checkout(Some(&order("created")), &query(7, "XTR", 50)).is_err());
+        assert!(validate_pre_checkout(Some(&order("paid")), &query(42, "XTR", 50)).is_err());
+        assert!(validate_pre_checkout(Some(&order("created")), &query(42, "USD", 50)).is_err());
+        assert!(validate_pre_checkout(Some(&order("created")), &query(42, "XTR", 49)).is_err());
+
+        let inactive = PendingOrder {
+            item_active: false,
+            ..order("created")
+        };
+        assert!(validate_pre_checkout(Some(&inactive), &query(42, "XTR", 50)).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use crate::models::realtime::{EventEnvelope, ServerEvent};
+
+/// Сколько последних событий сессии хранится для повтора после переподключения
+const REPLAY_BACKLOG: usize = 100;
+
+struct Session {
+    user_id: Uuid,
+    attached: bool,
+    detached_at: Option<Instant>,
+    last_tap_seq: u64,
+    next_event_id: u64,
+    backlog: VecDeque<EventEnvelope>,
+}
+
+/// Сессии WebSocket-соединений в памяти процесса.
+/// Сессия переживает обрыв соединения на `ttl` и продолжается по resume-токену.
+#[derive(Clone)]
+pub struct WsSessions {
+    inner: Arc<Mutex<HashMap<Uuid, Session>>>,
+    ttl: Duration,
+}
+
+pub struct Attached {
+    pub token: Uuid,
+    pub resumed: bool,
+    pub last_tap_seq: u64,
+    /// События, которые клиент не успел получить до обрыва
+    pub replay: Vec<EventEnvelope>,
+}
+
+impl WsSessions {
+    pub fn new(ttl: Duration) -> Self {
+        WsSessions {
+            inner: Arc::new(Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+
+    /// Продолжает сессию по токену или открывает новую.
+    /// Чужой, просроченный или уже подключённый токен открывает новую сессию.
+    pub fn attach(&self, user_id: Uuid, resume: Option<Uuid>, last_event_id: u64) -> Attached {
+        let mut sessions = self.inner.lock().unwrap();
+        let now = Instant::now();
+        sessions.retain(|_, s| s.attached || s.detached_at.is_some_and(|at| now - at < self.ttl));
+
+        if let Some(token) = resume {
+            if let Some(session) = sessions.get_mut(&token) {
+                if session.user_id == user_id && !session.attached {
+                    session.attached = true;
+                    session.detached_at = None;
+                    return Attached {
+                        token,
+                        resumed: true,
+                        last_tap_seq: session.last_tap_seq,
+                        replay: session.backlog.iter().filter(|e| e.id > last_event_id).cloned().collect(),
+                    };
+                }
+            }
+        }
+
+        let token = Uuid::new_v4();
+        sessions.insert(
+            token,
+            Session {
+                user_id,
+                attached: true,
+                detached_at: None,
+                last_tap_seq: 0,
+                next_event_id: 1,
+                backlog: VecDeque::new(),
+            },
+        );
+        Attached {
+            token,
+            resumed: false,
+            last_tap_seq: 0,
+            replay: Vec::new(),
+        }
+    }
+
+    pub fn detach(&self, token: Uuid) {
+        if let Some(session) = self.inner.lock().unwrap().get_mut(&token) {
+            session.attached = false;
+            session.detached_at = Some(Instant::now());
+        }
+    }
+
+    /// Нумерует событие и сохраняет его для повтора
+    pub fn record(&self, token: Uuid, event: ServerEvent) -> EventEnvelope {
+        let mut sessions = self.inner.lock().unwrap();
+        let Some(session) = sessions.get_mut(&token) else {
+            return EventEnvelope { id: 0, event };
+        };
+
+        let envelope = EventEnvelope {
+            id: session.next_event_id,
+            event,
+        };
+        session.next_event_id += 1;
+        if session.backlog.len() == REPLAY_BACKLOG {
+            session.backlog.pop_front();
+        }
+        session.backlog.push_back(envelope.clone());
+        envelope
+    }
+
+    pub fn last_tap_seq(&self, token: Uuid) -> u64 {
+        self.inner.lock().unwrap().get(&token).map_or(0, |s| s.last_tap_seq)
+    }
+
+    pub fn set_last_tap_seq(&self, token: Uuid, seq: u64) {
+        if let Some(session) = self.inner.lock().unwrap().get_mut(&token) {
+            session.last_tap_seq = seq;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(n: u64) -> ServerEvent {
+        ServerEvent::Error { message: n.to_string() }
+    }
+
+    #[test]
+    fn resume_replays_events_after_last_received() {
+        let sessions = WsSessions::new(Duration::from_secs(60));
+        let user_id = Uuid::new_v4();
+        let first = sessions.attach(user_id, None, 0);
+        assert!(!first.resumed);
+        for n in 1..=3 {
+            assert_eq!(sessions.record(first.token, error(n)).id, n);
+        }
+        sessions.set_last_tap_seq(first.token, 7);
+        sessions.detach(first.token);
+
+        let resumed = sessions.attach(user_id, Some(first.token), 1);
+        assert!(resumed.resumed);
+        assert_eq!(resumed.token, first.token);
+        assert_eq!(resumed.last_tap_seq, 7);
+        let replayed: Vec<u64> = resumed.replay.iter().map(|e| e.id).collect();
+        assert_eq!(replayed, [2, 3]);
+
+        // Нумерация продолжается с того же места
+        assert_eq!(sessions.record(resumed.token, error(4)).id, 4);
+    }
+
+    #[test]
+    fn foreign_attached_or_expired_token_opens_new_session() {
+        let sessions = WsSessions::new(Duration::from_millis(20));
+        let user_id = Uuid::new_v4();
+        let first = sessions.attach(user_id, None, 0);
+
+        // Соединение ещё открыто
+        let second = sessions.attach(user_id, Some(first.token), 0);
+        assert!(!second.resumed);
+        assert_ne!(second.token, first.token);
+
+        sessions.detach(first.token);
+        let stranger = sessions.attach(Uuid::new_v4(), Some(first.token), 0);
+        assert!(!stranger.resumed);
+
+        std::thread::sleep(Duration::from_millis(30));
+        let expired = sessions.attach(user_id, Some(first.token), 0);
+        assert!(!expired.resumed);
+        assert_eq!(expired.last_tap_seq, 0);
+    }
+
+    #[test]
+    fn backlog_keeps_only_latest_events() {
+        let sessions = WsSessions::new(Duration::from_secs(60));
+        let user_id = Uuid::new_v4();
+        let attached = sessions.attach(user_id, None, 0);
+        let total = REPLAY_BACKLOG as u64 + 10;
+        for n in 1..=total {
+            sessions.record(attached.token, error(n));
+        }
+        sessions.detach(attached.token);
+
+        let resumed = sessions.attach(user_id, Some(attached.token), 0);
+        assert_eq!(resumed.replay.len(), REPLAY_BACKLOG);
+        assert_eq!(resumed.replay.first().unwrap().id, 11);
+        assert_eq!(resumed.replay.last().unwrap().id, total);
+    }
+}
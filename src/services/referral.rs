@@ -0,0 +1,199 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::services::achievements::{self, Metric};
+use crate::services::balance::{self, LedgerKind};
+
+/// Префикс реферального start_param: t.me/bot/app?startapp=ref_<code>
+const START_PARAM_PREFIX: &str = "ref_";
+
+/// Извлекает реферальный код из start_param
+pub fn parse_start_param(start_param: &str) -> Option<&str> {
+    start_param
+        .strip_prefix(START_PARAM_PREFIX)
+        .filter(|code| !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Реферальный код первого входа: из startapp, а если Mini App открыли без него — из /start в боте
+pub fn first_login_code<'a>(start_param: Option<&'a str>, pending: Option<&'a str>) -> Option<&'a str> {
+    start_param
+        .and_then(parse_start_param)
+        .or_else(|| pending.and_then(parse_start_param))
+}
+
+/// Привязывает игрока к пригласившему по реферальному коду и начисляет бонусы обоим.
+///
+/// Возвращает `false`, если код не найден, игрок приглашает сам себя,
+/// привязка образует цикл или игрок уже привязан.
+pub async fn attribute(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    code: &str,
+) -> Result<bool, sqlx::Error> {
+    let referrer = sqlx::query!(
+        r#"SELECT id FROM users WHERE referral_code = $1"#,
+        code
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(referrer) = referrer else {
+        tracing::debug!("Referral code {} not found", code);
+        return Ok(false);
+    };
+
+    if referrer.id == user_id {
+        tracing::warn!("Self-referral rejected: user_id={}", user_id);
+        return Ok(false);
+    }
+
+    // Защита от циклов: игрок не должен быть в цепочке пригласивших referrer'а
+    let in_chain = sqlx::query_scalar!(
+        r#"
+        WITH RECURSIVE chain(id, depth) AS (
+            SELECT referred_by, 1 FROM users WHERE id = $1 AND referred_by IS NOT NULL
+            UNION ALL
+            SELECT u.referred_by, c.depth + 1
+            FROM users u
+            JOIN chain c ON u.id = c.id
+            WHERE u.referred_by IS NOT NULL AND c.depth < 100
+        )
+        SELECT EXISTS(SELECT 1 FROM chain WHERE id = $2) AS "exists!"
+        "#,
+        referrer.id,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if in_chain {
+        tracing::warn!("Referral cycle rejected: user_id={}, referrer_id={}", user_id, referrer.id);
+        return Ok(false);
+    }
+
+    let attributed = sqlx::query!(
+        r#"
+        UPDATE users
+        SET referred_by = $1, referred_at = now()
+        WHERE id = $2 AND referred_by IS NULL
+        "#,
+        referrer.id,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if !attributed {
+        return Ok(false);
+    }
+
+    let reference = user_id.to_string();
+    balance::credit(
+        &mut *conn,
+        referrer.id,
+        config.referral_bonus_referrer,
+        LedgerKind::ReferralBonus,
+        &reference,
+    )
+    .await?;
+    record_reward(&mut *conn, referrer.id, user_id, 1, config.referral_bonus_referrer).await?;
+
+    balance::credit(
+        &mut *conn,
+        user_id,
+        config.referral_bonus_referee,
+        LedgerKind::ReferralBonus,
+        &reference,
+    )
+    .await?;
+
+    let invited = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM users WHERE referred_by = $1"#,
+        referrer.id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    achievements::record(&mut *conn, config, referrer.id, Metric::Referrals, invited).await?;
+
+    tracing::info!("Referral attributed: user_id={}, referrer_id={}", user_id, referrer.id);
+
+    Ok(true)
+}
+
+/// Выплачивает пригласившим процент с заработка игрока по уровням из конфигурации
+pub async fn pay_uplines(
+    conn: &mut PgConnection,
+    config: &Config,
+    referee_id: Uuid,
+    amount: Decimal,
+    source_kind: LedgerKind,
+    source_reference: &str,
+) -> Result<(), sqlx::Error> {
+    if config.referral_level_percents.is_empty() {
+        return Ok(());
+    }
+
+    let uplines = sqlx::query!(
+        r#"
+        WITH RECURSIVE chain(id, level) AS (
+            SELECT referred_by, 1 FROM users WHERE id = $1 AND referred_by IS NOT NULL
+            UNION ALL
+            SELECT u.referred_by, c.level + 1
+            FROM users u
+            JOIN chain c ON u.id = c.id
+            WHERE u.referred_by IS NOT NULL AND c.level < $2
+        )
+        SELECT id AS "id!", level AS "level!" FROM chain ORDER BY level
+        "#,
+        referee_id,
+        config.referral_level_percents.len() as i32
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let reference = format!("{}:{}:{}", source_kind.as_str(), source_reference, referee_id);
+
+    for upline in uplines {
+        let percent = config.referral_level_percents[(upline.level - 1) as usize];
+        let payout = (amount * percent / Decimal::from(100)).round_dp(2);
+
+        if balance::credit(&mut *conn, upline.id, payout, LedgerKind::ReferralPayout, &reference).await? {
+            record_reward(&mut *conn, upline.id, referee_id, upline.level, payout).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn record_reward(
+    conn: &mut PgConnection,
+    referrer_id: Uuid,
+    referee_id: Uuid,
+    level: i32,
+    amount: Decimal,
+) -> Result<(), sqlx::Error> {
+    if amount <= Decimal::ZERO {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO referral_rewards (id, referrer_id, referee_id, level, amount)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        Uuid::new_v4(),
+        referrer_id,
+        referee_id,
+        level,
+        amount
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
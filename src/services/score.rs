@@ -0,0 +1,120 @@
+use rust_decimal::Decimal;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::achievement::UnlockedAchievement;
+use crate::models::league::LeagueStatus;
+use crate::services::achievements::{self, Metric};
+use crate::services::balance::{self, LedgerKind};
+use crate::models::event::AppEvent;
+use crate::services::{clans, energy, events, leagues, notifications, passive, tournaments};
+
+#[derive(Debug, Clone, Copy)]
+pub enum ScoreUpdate {
+    /// Итоговый счёт от клиента (`/game/update_score`); меньший счёт игнорируется,
+    /// прирост ограничен энергией
+    Total(i32),
+    /// Пачка тапов (WebSocket); принимается не больше, чем позволяет энергия
+    Taps(i32),
+}
+
+#[derive(Debug)]
+pub struct ScoreOutcome {
+    pub score: i32,
+    /// Сколько очков клиент пытался добавить
+    pub requested: i32,
+    /// Сколько очков добавлено этим обновлением
+    pub gained: i32,
+    pub energy: i32,
+    pub unlocked_achievements: Vec<UnlockedAchievement>,
+    pub earned_while_away: Decimal,
+    pub league: LeagueStatus,
+}
+
+/// Очки игрока; 0, если он ещё не играл
+pub async fn get(conn: &mut PgConnection, user_id: Uuid) -> Result<i32, sqlx::Error> {
+    let score = sqlx::query_scalar!(r#"SELECT score FROM scores WHERE user_id = $1"#, user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(score.unwrap_or(0))
+}
+
+/// Обновляет счёт и всё, что от него зависит: баланс, турниры, очки клана, энергию, достижения,
+/// пассивный доход и лигу. Вызывается внутри транзакции.
+pub async fn apply(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    update: ScoreUpdate,
+) -> Result<ScoreOutcome, sqlx::Error> {
+    let previous_score = sqlx::query_scalar!(
+        r#"SELECT score FROM scores WHERE user_id = $1 FOR UPDATE"#,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .unwrap_or(0);
+
+    let available = energy::available(&mut *conn, config, user_id).await?;
+    let (target, requested) = match update {
+        // Заявленный клиентом счёт растёт не больше, чем покрывает энергия: от сохранённого
+        // счёта зависят лиги, достижения, задания и все начисления
+        ScoreUpdate::Total(score) => (
+            score.min(previous_score.saturating_add(available)),
+            score.saturating_sub(previous_score).max(0),
+        ),
+        ScoreUpdate::Taps(count) => (previous_score.saturating_add(count.clamp(0, available)), count.max(0)),
+    };
+
+    let score = sqlx::query_scalar!(
+        r#"
+        INSERT INTO scores (id, user_id, score)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id)
+        DO UPDATE SET
+            score = GREATEST(scores.score, $3),
+            updated_at = now()
+        RETURNING score
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        target
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let gained = score - previous_score;
+
+    balance::credit_earning(
+        &mut *conn,
+        config,
+        user_id,
+        Decimal::from(gained),
+        LedgerKind::ScoreEarning,
+        &score.to_string(),
+    )
+    .await?;
+    tournaments::add_score(&mut *conn, user_id, i64::from(gained)).await?;
+    clans::add_score(&mut *conn, config, user_id, i64::from(gained)).await?;
+    let energy = energy::spend(&mut *conn, config, user_id, gained).await?;
+
+    let unlocked_achievements =
+        achievements::record(&mut *conn, config, user_id, Metric::Score, score.into()).await?;
+    let accrual = passive::accrue(&mut *conn, config, user_id).await?;
+    let league = leagues::sync(&mut *conn, config, user_id, score).await?;
+
+    if gained > 0 {
+        notifications::enqueue_overtaken(&mut *conn, config, user_id, previous_score, score).await?;
+        events::publish(&mut *conn, &AppEvent::ScoreChanged { user_id, score, previous_score }).await?;
+    }
+
+    Ok(ScoreOutcome {
+        score,
+        requested,
+        gained,
+        energy,
+        unlocked_achievements,
+        earned_while_away: accrual.earned,
+        league,
+    })
+}
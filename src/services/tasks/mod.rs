@@ -0,0 +1,85 @@
+mod verifiers;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::services::daily;
+use crate::utils::bot_api::BotApi;
+use crate::utils::errors::AppError;
+
+use verifiers::{DailyCheckIn, InviteFriends, JoinChannel, ReachScore, VisitLink};
+
+/// Всё, что нужно проверке задания: игрок, параметры задания и внешние зависимости
+pub struct TaskContext<'a> {
+    pub pool: &'a PgPool,
+    pub config: &'a Config,
+    pub bot_api: &'a BotApi,
+    pub user_id: Uuid,
+    pub telegram_id: i64,
+    pub params: &'a Value,
+    /// Когда игрок начал задание (если начинал)
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl TaskContext<'_> {
+    /// Числовой параметр задания; отсутствие параметра — ошибка конфигурации каталога
+    pub fn param_i64(&self, name: &str) -> Result<i64, AppError> {
+        self.params
+            .get(name)
+            .and_then(Value::as_i64)
+            .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Task param `{}` is missing", name)))
+    }
+
+    pub fn param_str(&self, name: &str) -> Result<&str, AppError> {
+        self.params
+            .get(name)
+            .and_then(Value::as_str)
+            .ok_or_else(|| AppError::Internal(anyhow::anyhow!("Task param `{}` is missing", name)))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verification {
+    Completed,
+    /// Задание ещё не выполнено, с пояснением для клиента
+    Pending(String),
+}
+
+/// Тип задания. Новые типы добавляются реализацией трейта и регистрацией в `verifier_for`
+#[async_trait]
+pub trait TaskVerifier: Send + Sync {
+    async fn verify(&self, ctx: &TaskContext<'_>) -> Result<Verification, AppError>;
+
+    /// Ежедневные задания можно выполнять заново каждый день
+    fn is_daily(&self) -> bool {
+        false
+    }
+}
+
+pub fn verifier_for(kind: &str) -> Option<&'static dyn TaskVerifier> {
+    match kind {
+        "join_channel" => Some(&JoinChannel),
+        "visit_link" => Some(&VisitLink),
+        "invite_friends" => Some(&InviteFriends),
+        "reach_score" => Some(&ReachScore),
+        "daily_checkin" => Some(&DailyCheckIn),
+        _ => None,
+    }
+}
+
+/// Период выполнения: пустая строка для разовых заданий, текущая дата для ежедневных
+pub fn current_period(verifier: &dyn TaskVerifier, config: &Config) -> String {
+    if verifier.is_daily() {
+        daily_period(config)
+    } else {
+        String::new()
+    }
+}
+
+/// Период ежедневных заданий — текущий игровой день
+pub fn daily_period(config: &Config) -> String {
+    daily::today(config).to_string()
+}
@@ -0,0 +1,117 @@
+use async_trait::async_trait;
+
+use super::{TaskContext, TaskVerifier, Verification};
+use crate::services::daily;
+use crate::utils::errors::AppError;
+
+/// Подписка на канал: проверяется через getChatMember (бот должен быть админом канала)
+pub struct JoinChannel;
+
+#[async_trait]
+impl TaskVerifier for JoinChannel {
+    async fn verify(&self, ctx: &TaskContext<'_>) -> Result<Verification, AppError> {
+        let chat_id = ctx.param_str("chat_id")?;
+        let member = ctx.bot_api
+            .get_chat_member(chat_id, ctx.telegram_id)
+            .await
+            .map_err(|e| {
+                tracing::error!("getChatMember failed for chat {}: {}", chat_id, e);
+                AppError::Internal(anyhow::anyhow!("Failed to check channel membership"))
+            })?;
+
+        if member.is_joined() {
+            Ok(Verification::Completed)
+        } else {
+            Ok(Verification::Pending(format!("Join {} first", chat_id)))
+        }
+    }
+}
+
+/// Переход по ссылке: проверить на сервере нельзя, поэтому засчитываем,
+/// если с начала задания прошло не меньше min_seconds
+pub struct VisitLink;
+
+#[async_trait]
+impl TaskVerifier for VisitLink {
+    async fn verify(&self, ctx: &TaskContext<'_>) -> Result<Verification, AppError> {
+        let Some(started_at) = ctx.started_at else {
+            return Ok(Verification::Pending("Open the link first".to_string()));
+        };
+
+        let min_seconds = ctx.params.get("min_seconds").and_then(|v| v.as_i64()).unwrap_or(0);
+        if (chrono::Utc::now() - started_at).num_seconds() < min_seconds {
+            return Ok(Verification::Pending("Link visit is not confirmed yet".to_string()));
+        }
+
+        Ok(Verification::Completed)
+    }
+}
+
+pub struct InviteFriends;
+
+#[async_trait]
+impl TaskVerifier for InviteFriends {
+    async fn verify(&self, ctx: &TaskContext<'_>) -> Result<Verification, AppError> {
+        let required = ctx.param_i64("count")?;
+        let invited = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM users WHERE referred_by = $1"#,
+            ctx.user_id
+        )
+        .fetch_one(ctx.pool)
+        .await?;
+
+        if invited >= required {
+            Ok(Verification::Completed)
+        } else {
+            Ok(Verification::Pending(format!("Invited {} of {} friends", invited, required)))
+        }
+    }
+}
+
+pub struct ReachScore;
+
+#[async_trait]
+impl TaskVerifier for ReachScore {
+    async fn verify(&self, ctx: &TaskContext<'_>) -> Result<Verification, AppError> {
+        let required = ctx.param_i64("score")?;
+        let score = sqlx::query_scalar!(
+            r#"SELECT score FROM scores WHERE user_id = $1"#,
+            ctx.user_id
+        )
+        .fetch_optional(ctx.pool)
+        .await?
+        .unwrap_or(0);
+
+        if i64::from(score) >= required {
+            Ok(Verification::Completed)
+        } else {
+            Ok(Verification::Pending(format!("Score {} of {}", score, required)))
+        }
+    }
+}
+
+/// Ежедневная отметка: засчитывается после POST /daily/checkin за текущий день
+pub struct DailyCheckIn;
+
+#[async_trait]
+impl TaskVerifier for DailyCheckIn {
+    async fn verify(&self, ctx: &TaskContext<'_>) -> Result<Verification, AppError> {
+        let checked_in = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM daily_checkins WHERE user_id = $1 AND day = $2) AS "exists!""#,
+            ctx.user_id,
+            daily::today(ctx.config)
+        )
+        .fetch_one(ctx.pool)
+        .await?;
+
+        if checked_in {
+            Ok(Verification::Completed)
+        } else {
+            Ok(Verification::Pending("Check in today first".to_string()))
+        }
+    }
+
+    fn is_daily(&self) -> bool {
+        true
+    }
+}
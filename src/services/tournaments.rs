@@ -0,0 +1,251 @@
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::services::balance::{self, LedgerKind};
+use crate::services::health::{self, Health};
+use crate::services::leagues;
+use crate::services::metrics;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JoinResult {
+    Joined,
+    NotFound,
+    Closed,
+    AlreadyJoined,
+    /// Лига игрока ниже минимальной для турнира
+    NotEligible,
+    InsufficientBalance,
+}
+
+/// Вступление в турнир с оплатой взноса. Вызывается внутри транзакции.
+pub async fn join(
+    conn: &mut PgConnection,
+    config: &Config,
+    user_id: Uuid,
+    tournament_id: Uuid,
+) -> Result<JoinResult, sqlx::Error> {
+    let tournament = sqlx::query!(
+        r#"
+        SELECT status, ends_at, entry_fee, min_league FROM tournaments
+        WHERE id = $1
+        FOR SHARE
+        "#,
+        tournament_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(tournament) = tournament else {
+        return Ok(JoinResult::NotFound);
+    };
+
+    if tournament.status != "open" || tournament.ends_at <= chrono::Utc::now() {
+        return Ok(JoinResult::Closed);
+    }
+
+    let already_joined = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM tournament_entries WHERE tournament_id = $1 AND user_id = $2
+        ) AS "exists!"
+        "#,
+        tournament_id,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if already_joined {
+        return Ok(JoinResult::AlreadyJoined);
+    }
+
+    if let Some(min_league) = tournament.min_league.as_deref() {
+        let score = sqlx::query_scalar!(r#"SELECT score FROM scores WHERE user_id = $1"#, user_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .unwrap_or(0);
+        let required = leagues::find_league(config, min_league).map(|(i, _)| i).unwrap_or(0);
+        if leagues::league_index(config, score) < required {
+            return Ok(JoinResult::NotEligible);
+        }
+    }
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO tournament_entries (tournament_id, user_id)
+        VALUES ($1, $2)
+        ON CONFLICT (tournament_id, user_id) DO NOTHING
+        "#,
+        tournament_id,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+    if !inserted {
+        return Ok(JoinResult::AlreadyJoined);
+    }
+
+    let paid = balance::debit(
+        &mut *conn,
+        user_id,
+        tournament.entry_fee,
+        LedgerKind::TournamentEntry,
+        &tournament_id.to_string(),
+    )
+    .await?;
+    if !paid {
+        return Ok(JoinResult::InsufficientBalance);
+    }
+
+    Ok(JoinResult::Joined)
+}
+
+/// Добавляет прирост очков во все идущие турниры игрока
+pub async fn add_score(conn: &mut PgConnection, user_id: Uuid, delta: i64) -> Result<(), sqlx::Error> {
+    if delta <= 0 {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE tournament_entries e
+        SET score = e.score + $2, updated_at = now()
+        FROM tournaments t
+        WHERE t.id = e.tournament_id
+            AND e.user_id = $1
+            AND t.status = 'open'
+            AND now() >= t.starts_at
+            AND now() < t.ends_at
+        "#,
+        user_id,
+        delta
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Закрывает турнир: фиксирует места, выплачивает призы и архивирует итоги.
+///
+/// Идемпотентно: турнир блокируется и закрывается только из статуса open,
+/// а выплаты защищены уникальной ссылкой в журнале баланса.
+/// Возвращает `false`, если турнир уже закрыт или не найден.
+pub async fn close(pool: &PgPool, config: &Config, tournament_id: Uuid) -> Result<bool, sqlx::Error> {
+    let started = Instant::now();
+    let mut tx = pool.begin().await?;
+
+    let tournament = sqlx::query!(
+        r#"
+        SELECT prizes, ends_at FROM tournaments
+        WHERE id = $1 AND status = 'open'
+        FOR UPDATE
+        "#,
+        tournament_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some(tournament) = tournament else {
+        return Ok(false);
+    };
+
+    // При равных очках выше тот, кто набрал их раньше
+    let standings = sqlx::query!(
+        r#"
+        SELECT user_id, score FROM tournament_entries
+        WHERE tournament_id = $1
+        ORDER BY score DESC, updated_at, joined_at
+        "#,
+        tournament_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let reference = tournament_id.to_string();
+    for (index, entry) in standings.iter().enumerate() {
+        let prize = tournament.prizes.get(index).copied().unwrap_or(Decimal::ZERO);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO tournament_results (tournament_id, user_id, rank, score, prize)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (tournament_id, user_id) DO NOTHING
+            "#,
+            tournament_id,
+            entry.user_id,
+            index as i32 + 1,
+            entry.score,
+            prize
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        balance::credit_earning(&mut tx, config, entry.user_id, prize, LedgerKind::TournamentPrize, &reference)
+            .await?;
+    }
+
+    sqlx::query!(
+        r#"UPDATE tournaments SET status = 'closed', closed_at = now() WHERE id = $1"#,
+        tournament_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    // Админ может закрыть турнир досрочно — тогда задержки нет
+    let lag = (Utc::now() - tournament.ends_at).to_std().unwrap_or_default();
+    metrics::record_payout(started.elapsed(), lag);
+    tracing::info!("Tournament closed: tournament_id={}, participants={}", tournament_id, standings.len());
+
+    Ok(true)
+}
+
+/// Закрывает все турниры, время которых вышло
+pub async fn close_due(pool: &PgPool, config: &Config) -> Result<usize, sqlx::Error> {
+    let due = sqlx::query_scalar!(
+        r#"SELECT id FROM tournaments WHERE status = 'open' AND ends_at <= now() ORDER BY ends_at"#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut closed = 0;
+    for tournament_id in due {
+        if close(pool, config, tournament_id).await? {
+            closed += 1;
+        }
+    }
+
+    Ok(closed)
+}
+
+/// Фоновая задача выплат: периодически закрывает завершившиеся турниры.
+/// При остановке сервера доводит текущий цикл до конца и выходит.
+pub async fn run_payout_worker(pool: PgPool, config: Config, health: Health, shutdown: CancellationToken) {
+    let period = Duration::from_secs(config.tournament_close_interval_secs.max(1));
+    let mut interval = tokio::time::interval(period);
+    health.register_periodic(health::TOURNAMENT_PAYOUTS, period);
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        if let Err(e) = close_due(&pool, &config).await {
+            tracing::error!("Failed to close due tournaments: {}", e);
+        }
+        health.beat(health::TOURNAMENT_PAYOUTS);
+    }
+
+    tracing::info!("Tournament payout worker stopped");
+}
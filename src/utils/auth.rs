@@ -0,0 +1,88 @@
+use axum::http::{HeaderMap, Uri};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::utils::errors::AppError;
+use crate::utils::jwt;
+
+// Извлекаем JWT токен из заголовка Authorization
+pub fn extract_user_id(
+    headers: &HeaderMap,
+    jwt_secret: &str,
+) -> Result<Uuid, AppError> {
+    let auth_header = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+    
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AppError::Unauthorized)?;
+    
+    user_id_from_token(token, jwt_secret)
+}
+
+/// Для WebSocket и SSE: браузер не может передать заголовок Authorization,
+/// поэтому токен можно передать в query-параметре `token`
+pub fn extract_user_id_or_token(
+    headers: &HeaderMap,
+    token: Option<&str>,
+    jwt_secret: &str,
+) -> Result<Uuid, AppError> {
+    match token {
+        Some(token) => user_id_from_token(token, jwt_secret),
+        None => extract_user_id(headers, jwt_secret),
+    }
+}
+
+/// URI для логов: JWT из query-параметра `token` заменяется на `***`
+pub fn redact_token(uri: &Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.path().to_string();
+    };
+    let query: Vec<&str> = query
+        .split('&')
+        .map(|pair| if pair.starts_with("token=") { "token=***" } else { pair })
+        .collect();
+    format!("{}?{}", uri.path(), query.join("&"))
+}
+
+fn user_id_from_token(token: &str, jwt_secret: &str) -> Result<Uuid, AppError> {
+    let claims = jwt::verify_jwt(token, jwt_secret)
+        .map_err(|_| AppError::Unauthorized)?;
+    
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Validation("Invalid user ID in token".to_string()))?;
+    
+    // Дальше все записи лога этого запроса несут user_id
+    tracing::Span::current().record("user_id", tracing::field::display(user_id));
+    Ok(user_id)
+}
+
+/// Проверяет заголовок X-Admin-Token для админских эндпоинтов
+pub fn require_admin(headers: &HeaderMap, config: &Config) -> Result<(), AppError> {
+    require_secret_header(headers, "x-admin-token", config.admin_token.as_deref())
+}
+
+/// Проверяет, что webhook пришёл от Telegram: секрет задаётся при setWebhook
+pub fn require_webhook_secret(headers: &HeaderMap, config: &Config) -> Result<(), AppError> {
+    require_secret_header(headers, "x-telegram-bot-api-secret-token", config.telegram_webhook_secret.as_deref())
+}
+
+fn require_secret_header(headers: &HeaderMap, name: &str, expected: Option<&str>) -> Result<(), AppError> {
+    let expected = expected.ok_or(AppError::Unauthorized)?;
+    let provided = headers
+        .get(name)
+        .and_then(|h| h.to_str().ok())
+        .ok_or(AppError::Unauthorized)?;
+    
+    // Сравнение за постоянное время, чтобы не подсказывать секрет по таймингу
+    let matches = expected.len() == provided.len()
+        && expected.bytes().zip(provided.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0;
+    
+    if matches {
+        Ok(())
+    } else {
+        Err(AppError::Unauthorized)
+    }
+}
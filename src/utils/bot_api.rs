@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+
+use crate::config::Config;
+use crate::models::bot::{CreateInvoiceLink, SendMessage, SentMessage};
+
+#[derive(Error, Debug)]
+pub enum BotApiError {
+    /// Без URL запроса: в нём токен бота
+    #[error("HTTP error: {0}")]
+    Http(reqwest::Error),
+
+    #[error("Bot API error {code}: {description}")]
+    Api {
+        code: i64,
+        description: String,
+        retry_after: Option<u64>,
+    },
+}
+
+impl BotApiError {
+    /// Классифицированная ошибка для `notifications.last_error`: без текста транспорта
+    pub fn summary(&self) -> String {
+        match self {
+            BotApiError::Http(e) if e.is_timeout() => "timeout".to_string(),
+            BotApiError::Http(e) if e.is_connect() => "connection failed".to_string(),
+            BotApiError::Http(e) if e.is_decode() => "invalid response".to_string(),
+            BotApiError::Http(e) => match e.status() {
+                Some(status) => format!("HTTP {}", status.as_u16()),
+                None => "request failed".to_string(),
+            },
+            BotApiError::Api { code, description, .. } => format!("{}: {}", code, description),
+        }
+    }
+}
+
+/// Убирает URL с токеном из ошибки транспорта: её текст попадает в логи и в БД
+fn redact(error: reqwest::Error) -> BotApiError {
+    BotApiError::Http(error.without_url())
+}
+
+/// Ответ Bot API: {"ok": true, "result": ...} или {"ok": false, "error_code": ..., "description": ...}
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    error_code: Option<i64>,
+    description: Option<String>,
+    parameters: Option<ResponseParameters>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseParameters {
+    retry_after: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatMember {
+    pub status: String,
+    /// Только для status = "restricted"
+    #[serde(default)]
+    pub is_member: bool,
+}
+
+impl ChatMember {
+    pub fn is_joined(&self) -> bool {
+        match self.status.as_str() {
+            "creator" | "administrator" | "member" => true,
+            "restricted" => self.is_member,
+            _ => false,
+        }
+    }
+}
+
+/// Клиент Telegram Bot API. base_url настраивается, чтобы в тестах и локально
+/// направлять запросы на мок-сервер вместо api.telegram.org
+#[derive(Clone)]
+pub struct BotApi {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl BotApi {
+    pub fn new(base_url: &str, token: &str, timeout: Duration) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("Failed to build HTTP client"),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            &config.telegram_api_base_url,
+            &config.telegram_bot_token,
+            Duration::from_millis(config.telegram_api_timeout_ms),
+        )
+    }
+
+    async fn call<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &P,
+    ) -> Result<R, BotApiError> {
+        let url = format!("{}/bot{}/{}", self.base_url, self.token, method);
+        let response: ApiResponse<R> = self.client
+            .post(url)
+            .json(params)
+            .send()
+            .await
+            .map_err(redact)?
+            .json()
+            .await
+            .map_err(redact)?;
+
+        match response.result {
+            Some(result) if response.ok => Ok(result),
+            _ => Err(BotApiError::Api {
+                code: response.error_code.unwrap_or_default(),
+                description: response.description.unwrap_or_else(|| "Unknown error".to_string()),
+                retry_after: response.parameters.and_then(|p| p.retry_after),
+            }),
+        }
+    }
+
+    pub async fn send_message(&self, message: &SendMessage) -> Result<SentMessage, BotApiError> {
+        self.call("sendMessage", message).await
+    }
+
+    /// Ссылка на оплату инвойса
+    pub async fn create_invoice_link(&self, invoice: &CreateInvoiceLink) -> Result<String, BotApiError> {
+        self.call("createInvoiceLink", invoice).await
+    }
+
+    pub async fn refund_star_payment(&self, user_id: i64, telegram_payment_charge_id: &str) -> Result<bool, BotApiError> {
+        self.call(
+            "refundStarPayment",
+            &json!({ "user_id": user_id, "telegram_payment_charge_id": telegram_payment_charge_id }),
+        )
+        .await
+    }
+
+    pub async fn get_chat_member(&self, chat_id: &str, user_id: i64) -> Result<ChatMember, BotApiError> {
+        self.call("getChatMember", &json!({ "chat_id": chat_id, "user_id": user_id }))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    const TOKEN: &str = "123456:secret-bot-token";
+
+    #[tokio::test]
+    async fn connect_error_does_not_leak_token() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let api = BotApi::new(&format!("http://{}", addr), TOKEN, Duration::from_millis(500));
+        let error = api.get_chat_member("@channel", 1).await.unwrap_err();
+
+        assert!(matches!(error, BotApiError::Http(_)));
+        assert!(!error.to_string().contains(TOKEN), "{}", error);
+        assert!(!format!("{:?}", error).contains(TOKEN), "{:?}", error);
+    }
+}
@@ -6,6 +6,8 @@ use axum::{
 use serde_json::json;
 use thiserror::Error;
 
+use crate::utils::request_id;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
@@ -44,10 +46,14 @@ impl IntoResponse for AppError {
             }
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "error": error_message
-        }));
+        });
+        // По id из ответа ошибку можно найти в логах
+        if let Some(request_id) = request_id::current() {
+            body["request_id"] = request_id.into();
+        }
 
-        (status, body).into_response()
+        (status, Json(body)).into_response()
     }
 }
@@ -1,3 +1,6 @@
 pub mod errors;
 pub mod telegram;
 pub mod jwt;
+pub mod auth;
+pub mod bot_api;
+pub mod request_id;
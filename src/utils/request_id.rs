@@ -0,0 +1,60 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+pub const HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Длиннее не принимаем: значение попадает в каждую строку лога
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id текущего запроса; None вне обработки HTTP-запроса (фоновые задачи, тесты сервисов)
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Middleware: берёт X-Request-Id клиента или генерирует новый, кладёт его в заголовки запроса
+/// (оттуда его читает span трассировки) и возвращает в ответе
+pub async fn propagate(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid(value))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let header = HeaderValue::from_str(&request_id).expect("request id is visible ASCII");
+    request.headers_mut().insert(HEADER, header.clone());
+
+    let mut response = REQUEST_ID.scope(request_id, next.run(request)).await;
+    response.headers_mut().insert(HEADER, header);
+    response
+}
+
+fn is_valid(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_LEN
+        && value.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_common_formats_and_rejects_the_rest() {
+        assert!(is_valid("0b6f3c5e-6a4d-4c1a-9a57-3f0c2f1d9e21"));
+        assert!(is_valid("req_01HZX3K8.retry:2"));
+        assert!(!is_valid(""));
+        assert!(!is_valid("with space"));
+        assert!(!is_valid("line\nbreak"));
+        assert!(!is_valid(&"a".repeat(MAX_LEN + 1)));
+    }
+}
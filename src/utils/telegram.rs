@@ -2,8 +2,22 @@ use hmac::{Hmac, Mac};
 use sha2::Sha256;
 use hex;
 use base64::{Engine as _, engine::general_purpose};
+use std::borrow::Cow;
 use std::collections::BTreeMap;
-use percent_encoding::percent_decode_str;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Экранируется всё, кроме незарезервированных символов RFC 3986 (как в initData от Telegram)
+const INIT_DATA_VALUE: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+/// Декодирует значение initData как application/x-www-form-urlencoded: `+` означает пробел
+fn decode_value(value: &str) -> Result<String, std::str::Utf8Error> {
+    let value = if value.contains('+') {
+        Cow::Owned(value.replace('+', " "))
+    } else {
+        Cow::Borrowed(value)
+    };
+    percent_decode_str(&value).decode_utf8().map(Cow::into_owned)
+}
 
 pub fn verify_telegram_auth(data: &BTreeMap<String, String>, hash: &str, bot_token: &str, dev_mode: bool) -> bool {
     // В dev-режиме пропускаем мок-хэши для локальной разработки
@@ -82,6 +96,10 @@ pub fn verify_telegram_auth_from_init_data(
     // Сначала проходим по всем параметрам, чтобы найти hash и signature
     for pair in init_data.split('&') {
         if let Some((key, value)) = pair.split_once('=') {
+            // Telegram не повторяет ключи; дубль неоднозначен (какой из user проверен подписью?)
+            if all_params.iter().any(|(existing, _)| *existing == key) {
+                return Err(format!("Duplicate parameter {} in initData", key));
+            }
             if key == "hash" {
                 hash_value = Some(value);
             } else if key == "signature" {
@@ -134,51 +152,19 @@ pub fn verify_telegram_auth_from_init_data(
     tracing::debug!("   Параметров (кроме hash, но включая query_id и signature): {}", params.len());
     tracing::debug!("   Параметры до сортировки: {:?}", params.iter().map(|(k, _)| *k).collect::<Vec<_>>());
     
-    // Сортируем параметры по ключам (алфавитный порядок) - это требование Telegram
-    // ВАЖНО: значения остаются оригинальными из initData, сохраняя порядок внутри (например, в объекте user)
-    params.sort_by(|a, b| a.0.cmp(b.0));
-    
-    tracing::debug!("   Параметры после сортировки: {:?}", params.iter().map(|(k, _)| *k).collect::<Vec<_>>());
-    
-    // Формируем check_string из оригинальной строки (без параметра hash)
-    // ВАЖНО: Согласно официальной документации Telegram, значения нужно декодировать из URL-encoding
-    // перед включением в check_string. В примере документации user показан как JSON объект, а не URL-encoded
+    // Сортируем параметры по ключам (алфавитный порядок) и декодируем значения - это требование Telegram
     // Пример: user=%7B%22id%22%3A279058397%7D -> user={"id":279058397}
-    let mut check_string_parts = Vec::new();
-    for (key, value) in &params {
-        // Декодируем значение из URL-encoding перед включением в check_string
-        let decoded_value = percent_decode_str(value)
-            .decode_utf8()
-            .map_err(|e| format!("Failed to decode value for key {}: {}", key, e))?;
-        tracing::debug!("   Параметр {}: оригинальное значение: {:?}, декодированное: {:?}", key, value, decoded_value);
-        check_string_parts.push(format!("{}={}", key, decoded_value));
-    }
-    let check_string = check_string_parts.join("\n");
+    let check_string = build_check_string(&params)?;
     
     tracing::debug!("   Сформированный check_string: {:?}", check_string);
-    tracing::debug!("   check_string (bytes): {:?}", check_string.as_bytes());
     
-    // Вычисляем секретный ключ согласно документации Telegram:
-    // secret_key = HMAC-SHA256(key="WebAppData", message=bot_token)
-    let mut mac_key = Hmac::<Sha256>::new_from_slice(b"WebAppData")
-        .map_err(|e| format!("Failed to create HMAC key: {}", e))?;
-    mac_key.update(bot_token.as_bytes());
-    let secret_key = mac_key.finalize().into_bytes();
-    
-    tracing::debug!("   Секретный ключ (первые 16 байт): {:?}", &secret_key[..16.min(secret_key.len())]);
-    
-    // Создаем HMAC для проверки подписи
-    let mut mac = Hmac::<Sha256>::new_from_slice(&secret_key)
-        .map_err(|e| format!("Failed to create HMAC: {}", e))?;
-    mac.update(check_string.as_bytes());
-    
-    let result = mac.finalize().into_bytes();
-    let computed_hash_hex = hex::encode(result);
+    let result = webapp_signature(&check_string, bot_token);
+    let computed_hash_hex = hex::encode(&result);
     
     // Telegram может отправлять hash в формате hex, base64 или base64url
     // Пробуем все варианты
-    let computed_hash_base64 = general_purpose::STANDARD.encode(result);
-    let computed_hash_base64url = general_purpose::URL_SAFE_NO_PAD.encode(result);
+    let computed_hash_base64 = general_purpose::STANDARD.encode(&result);
+    let computed_hash_base64url = general_purpose::URL_SAFE_NO_PAD.encode(&result);
     
     tracing::debug!("   Вычисленный hash (hex): {}", computed_hash_hex);
     tracing::debug!("   Вычисленный hash (base64): {}", computed_hash_base64);
@@ -205,14 +191,11 @@ pub fn verify_telegram_auth_from_init_data(
         None
     };
     
-    // Преобразуем result в Vec<u8> для сравнения
-    let result_bytes: Vec<u8> = result.to_vec();
-    
     // Проверяем все форматы (прямое сравнение строк и байтов)
     let hash_matches = computed_hash_hex == received_hash 
         || computed_hash_base64 == received_hash 
         || computed_hash_base64url == received_hash
-        || received_hash_bytes.map(|h| h == result_bytes).unwrap_or(false);
+        || received_hash_bytes.map(|h| h == result).unwrap_or(false);
     
     tracing::debug!("   Hash совпадает: {}", hash_matches);
     
@@ -231,14 +214,62 @@ pub fn verify_telegram_auth_from_init_data(
     Ok(true)
 }
 
+/// Собирает data_check_string: параметры без hash, отсортированные по ключу,
+/// со значениями после URL-декодирования, через `\n`
+pub fn build_check_string(params: &[(&str, &str)]) -> Result<String, String> {
+    let mut sorted = params.to_vec();
+    sorted.sort_by(|a, b| a.0.cmp(b.0));
+    
+    let mut parts = Vec::with_capacity(sorted.len());
+    for (key, value) in sorted {
+        let decoded_value = decode_value(value)
+            .map_err(|e| format!("Failed to decode value for key {}: {}", key, e))?;
+        parts.push(format!("{}={}", key, decoded_value));
+    }
+    Ok(parts.join("\n"))
+}
+
+/// Подпись check_string для Mini App: HMAC-SHA256(key=HMAC-SHA256("WebAppData", bot_token))
+pub fn webapp_signature(check_string: &str, bot_token: &str) -> Vec<u8> {
+    let mut mac_key = Hmac::<Sha256>::new_from_slice(b"WebAppData")
+        .expect("HMAC can take key of any size");
+    mac_key.update(bot_token.as_bytes());
+    let secret_key = mac_key.finalize().into_bytes();
+    
+    let mut mac = Hmac::<Sha256>::new_from_slice(&secret_key)
+        .expect("HMAC can take key of any size");
+    mac.update(check_string.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Собирает строку initData с hash, как её передаёт Telegram в Mini App.
+/// Значения передаются в исходном виде и URL-кодируются здесь.
+pub fn sign_init_data(params: &[(&str, &str)], bot_token: &str) -> String {
+    let encoded: Vec<(&str, String)> = params
+        .iter()
+        .map(|(key, value)| (*key, utf8_percent_encode(value, INIT_DATA_VALUE).to_string()))
+        .collect();
+    let pairs: Vec<(&str, &str)> = encoded.iter().map(|(key, value)| (*key, value.as_str())).collect();
+    
+    // Закодированные значения всегда декодируются обратно
+    let check_string = build_check_string(&pairs).expect("percent-encoded UTF-8 decodes");
+    let hash = hex::encode(webapp_signature(&check_string, bot_token));
+    
+    encoded
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .chain(std::iter::once(format!("hash={}", hash)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
 /// Парсит объект user из initData строки
 pub fn parse_user_from_init_data(init_data: &str) -> Result<serde_json::Value, String> {
     for pair in init_data.split('&') {
         if let Some((key, value)) = pair.split_once('=') {
             if key == "user" {
                 // Декодируем URL-encoded значение
-                let decoded = percent_decode_str(value)
-                    .decode_utf8()
+                let decoded = decode_value(value)
                     .map_err(|e| format!("Failed to decode user param: {}", e))?;
                 
                 // Парсим JSON
@@ -253,3 +284,176 @@ pub fn parse_user_from_init_data(init_data: &str) -> Result<serde_json::Value, S
     
     Err("User parameter not found in initData".to_string())
 }
+
+/// Возвращает декодированное значение параметра из initData (например, start_param)
+pub fn parse_param_from_init_data(init_data: &str, name: &str) -> Option<String> {
+    init_data
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| decode_value(value).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+    use proptest::prelude::*;
+    
+    // Официальных векторов для HMAC-подписи initData Telegram не публикует. Векторы ниже получены
+    // независимой эталонной реализацией алгоритма из документации (Python: urllib.parse.parse_qsl,
+    // hmac, hashlib) и проверены ею же; третий закодирован через quote_plus (`+` вместо пробела).
+    const KAT_TOKEN: &str = "7342037359:AAHJ-kat-test-token";
+    
+    const KAT_BASIC: &str = "query_id=AAHdF6IQAAAAAN0XohDhrOrc&user=%7B%22id%22%3A279058397%2C%22first_name%22%3A%22Vladislav%22%2C%22last_name%22%3A%22Kibenko%22%2C%22username%22%3A%22vdkfrost%22%2C%22language_code%22%3A%22ru%22%2C%22is_premium%22%3Atrue%2C%22allows_write_to_pm%22%3Atrue%7D&auth_date=1662771648&hash=233fe517fb29d1c7cf344851be748df41cc33802debe45182e2fc01f76cc0a23";
+    
+    /// Bot API 8.0+: signature входит в check_string наравне с остальными полями
+    const KAT_SIGNATURE: &str = "query_id=AAHdF6IQAAAAAN0XohDhrOrc&user=%7B%22id%22%3A279058397%2C%22first_name%22%3A%22Vladislav%22%2C%22last_name%22%3A%22Kibenko%22%2C%22username%22%3A%22vdkfrost%22%2C%22language_code%22%3A%22ru%22%2C%22is_premium%22%3Atrue%2C%22allows_write_to_pm%22%3Atrue%7D&auth_date=1733584787&signature=6fbdaab833d39f54518bd5c3eb3f511d035e68cb-kat&hash=2fd581e6bc0697771816220695d2fab60338494cbec863aac9b65be1c7fe37ef";
+    
+    const KAT_UNICODE_PLUS: &str = "user=%7B%22id%22%3A42%2C%22first_name%22%3A%22%D0%97%D0%BE%D1%80%D0%B3+%F0%9F%91%BD%22%2C%22last_name%22%3A%22%D0%B8%D0%B7+%D0%90%D0%BB%D1%8C%D1%84%D1%8B+%D0%A6%D0%B5%D0%BD%D1%82%D0%B0%D0%B2%D1%80%D0%B0%22%2C%22username%22%3A%22zorg%22%7D&chat_instance=-8264938491035521&chat_type=private&start_param=ref_ABC123&auth_date=1700000000&hash=6b6b4d344a18d6e4bccf3ce3ccafbac31ffa49fa7e140cda1cbfe31db3d2235b";
+    
+    fn verify(init_data: &str, token: &str) -> bool {
+        verify_telegram_auth_from_init_data(init_data, token, false).is_ok()
+    }
+    
+    fn digest(pairs: &[(String, String)], token: &str) -> Vec<u8> {
+        let mut sorted: Vec<_> = pairs.iter().collect();
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+        let check_string = sorted.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("\n");
+        
+        let mut secret = Hmac::<Sha256>::new_from_slice(b"WebAppData").unwrap();
+        secret.update(token.as_bytes());
+        let mut mac = Hmac::<Sha256>::new_from_slice(&secret.finalize().into_bytes()).unwrap();
+        mac.update(check_string.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+    
+    /// Кодирует пары в том порядке, в котором они переданы; пробелы — `+` или `%20`
+    fn encode(pairs: &[(String, String)], hash: &str, plus_for_space: bool) -> String {
+        pairs
+            .iter()
+            .map(|(k, v)| {
+                let value = utf8_percent_encode(v, NON_ALPHANUMERIC).to_string();
+                let value = if plus_for_space { value.replace("%20", "+") } else { value };
+                format!("{}={}", k, value)
+            })
+            .chain(std::iter::once(format!("hash={}", hash)))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+    
+    fn pairs(user_id: i64, first_name: &str) -> Vec<(String, String)> {
+        let user = serde_json::json!({ "id": user_id, "first_name": first_name }).to_string();
+        vec![
+            ("query_id".to_string(), "AAHdF6IQAAAAAN0XohDhrOrc".to_string()),
+            ("user".to_string(), user),
+            ("auth_date".to_string(), "1700000000".to_string()),
+        ]
+    }
+    
+    #[test]
+    fn known_answer_vectors_verify() {
+        assert!(verify(KAT_BASIC, KAT_TOKEN));
+        assert!(verify(KAT_SIGNATURE, KAT_TOKEN));
+        assert!(verify(KAT_UNICODE_PLUS, KAT_TOKEN));
+        
+        assert!(!verify(KAT_BASIC, "7342037359:other-token"));
+        assert!(!verify(&KAT_BASIC.replace("auth_date=1662771648", "auth_date=1662771649"), KAT_TOKEN));
+    }
+    
+    #[test]
+    fn signature_is_part_of_check_string() {
+        let without_signature = KAT_SIGNATURE.replace("&signature=6fbdaab833d39f54518bd5c3eb3f511d035e68cb-kat", "");
+        assert!(!verify(&without_signature, KAT_TOKEN));
+    }
+    
+    #[test]
+    fn unicode_and_plus_encoded_values_are_decoded() {
+        let user = parse_user_from_init_data(KAT_UNICODE_PLUS).unwrap();
+        assert_eq!(user["first_name"], "Зорг 👽");
+        assert_eq!(user["last_name"], "из Альфы Центавра");
+        assert_eq!(parse_param_from_init_data(KAT_UNICODE_PLUS, "start_param").as_deref(), Some("ref_ABC123"));
+        
+        // Тот же вектор с %20 вместо + даёт ту же подпись
+        assert!(verify(&KAT_UNICODE_PLUS.replace('+', "%20"), KAT_TOKEN));
+    }
+    
+    #[test]
+    fn duplicated_keys_are_rejected() {
+        let duplicated_user = KAT_BASIC.replacen("&auth_date", "&user=%7B%22id%22%3A1%7D&auth_date", 1);
+        assert!(verify_telegram_auth_from_init_data(&duplicated_user, KAT_TOKEN, false)
+            .unwrap_err()
+            .contains("Duplicate"));
+        
+        let duplicated_hash = format!("{}&hash=00", KAT_BASIC);
+        assert!(!verify(&duplicated_hash, KAT_TOKEN));
+    }
+    
+    #[test]
+    fn sign_init_data_matches_reference_signature() {
+        let user = r#"{"id":279058397,"first_name":"Vladislav","last_name":"Kibenko","username":"vdkfrost","language_code":"ru","is_premium":true,"allows_write_to_pm":true}"#;
+        let init_data = sign_init_data(
+            &[("query_id", "AAHdF6IQAAAAAN0XohDhrOrc"), ("user", user), ("auth_date", "1662771648")],
+            KAT_TOKEN,
+        );
+        
+        assert!(init_data.ends_with("&hash=233fe517fb29d1c7cf344851be748df41cc33802debe45182e2fc01f76cc0a23"));
+        assert!(verify(&init_data, KAT_TOKEN));
+    }
+    
+    #[test]
+    fn hash_encodings_and_mock_hash() {
+        let pairs = pairs(7, "Zorg");
+        let digest = digest(&pairs, KAT_TOKEN);
+        
+        assert!(verify(&encode(&pairs, &hex::encode(&digest), false), KAT_TOKEN));
+        assert!(verify(&encode(&pairs, &general_purpose::STANDARD.encode(&digest), false), KAT_TOKEN));
+        assert!(verify(&encode(&pairs, &general_purpose::URL_SAFE_NO_PAD.encode(&digest), false), KAT_TOKEN));
+        assert!(!verify(&encode(&pairs, "", false), KAT_TOKEN));
+        
+        let mock = encode(&pairs, "mock_hash_for_development_x", false);
+        assert!(!verify(&mock, KAT_TOKEN));
+        assert!(verify_telegram_auth_from_init_data(&mock, KAT_TOKEN, true).is_ok());
+    }
+    
+    proptest! {
+        #[test]
+        fn signed_init_data_round_trips(
+            user_id in 1i64..10_000_000_000,
+            first_name in "\\PC{0,24}",
+            start_param in proptest::option::of("[A-Za-z0-9_]{1,32}"),
+            plus_for_space: bool,
+            order in Just(vec![0usize, 1, 2, 3]).prop_shuffle(),
+        ) {
+            let mut pairs = pairs(user_id, &first_name);
+            if let Some(start_param) = start_param {
+                pairs.push(("start_param".to_string(), start_param));
+            }
+            let hash = hex::encode(digest(&pairs, KAT_TOKEN));
+            
+            // Порядок полей в строке не влияет на подпись
+            let shuffled: Vec<_> = order.iter().filter_map(|&i| pairs.get(i).cloned()).collect();
+            let init_data = encode(&shuffled, &hash, plus_for_space);
+            
+            prop_assert!(verify(&init_data, KAT_TOKEN));
+            let user = parse_user_from_init_data(&init_data).unwrap();
+            prop_assert_eq!(user["id"].as_i64(), Some(user_id));
+            prop_assert_eq!(user["first_name"].as_str(), Some(first_name.as_str()));
+        }
+        
+        #[test]
+        fn tampered_or_foreign_init_data_is_rejected(
+            user_id in 1i64..10_000_000_000,
+            first_name in "\\PC{0,24}",
+        ) {
+            let pairs = pairs(user_id, &first_name);
+            let hash = hex::encode(digest(&pairs, KAT_TOKEN));
+            
+            prop_assert!(!verify(&encode(&pairs, &hash, false), "7342037359:other-token"));
+            
+            let mut tampered = pairs.clone();
+            tampered[1].1 = serde_json::json!({ "id": user_id + 1, "first_name": first_name }).to_string();
+            prop_assert!(!verify(&encode(&tampered, &hash, false), KAT_TOKEN));
+        }
+    }
+}
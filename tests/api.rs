@@ -0,0 +1,602 @@
+mod common;
+
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use alien_tap_backend::db;
+use alien_tap_backend::services::{health, leagues};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::json;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use common::{metrics_handle, mint_init_data, telegram_user, TestApp, BOT_TOKEN};
+
+#[tokio::test]
+async fn auth_accepts_signed_init_data_and_rejects_forgeries() {
+    let app = TestApp::spawn().await;
+
+    let (token, user_id) = app.login(5001).await;
+    assert!(!token.is_empty());
+
+    // Повторный вход возвращает того же игрока
+    let (_, again) = app.login(5001).await;
+    assert_eq!(again, user_id);
+
+    let (status, profile) = app.get("/me", Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(profile["telegram_id"], 5001);
+    assert_eq!(profile["username"], "pilot5001");
+
+    // Подпись другим токеном бота
+    let forged = mint_init_data("999:other-bot", &telegram_user(5002), &[]);
+    let (status, _) = app.post("/auth/telegram", None, json!({ "initData": forged })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Подменённый пользователь при исходной подписи
+    let signed = mint_init_data(BOT_TOKEN, &telegram_user(5003), &[]);
+    let tampered = signed.replace("5003", "5004");
+    let (status, _) = app.post("/auth/telegram", None, json!({ "initData": tampered })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Мок-хэш разрешён только в DEV_MODE
+    let mock = "user=%7B%22id%22%3A5005%7D&auth_date=1&hash=mock_hash_for_development_x";
+    let (status, _) = app.post("/auth/telegram", None, json!({ "initData": mock })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app.get("/me", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn profile_reports_stored_league() {
+    let app = TestApp::spawn().await;
+    let (token, user_id) = app.login(5011).await;
+
+    let (status, profile) = app.get("/me", Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(profile["league"]["league"], app.config.leagues[0].name);
+
+    // Лига из user_leagues, а не пересчёт по очкам (например, после смены порогов)
+    sqlx::query("INSERT INTO user_leagues (user_id, league) VALUES ($1, $2)")
+        .bind(user_id)
+        .bind(&app.config.leagues[1].name)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let (_, profile) = app.get("/me", Some(&token)).await;
+    assert_eq!(profile["score"], 0);
+    assert_eq!(profile["league"]["league"], app.config.leagues[1].name);
+    assert_eq!(profile["league"]["next_league"], app.config.leagues[2].name);
+    assert_eq!(profile["league"]["next_league_score"], app.config.leagues[2].min_score);
+}
+
+#[tokio::test]
+async fn score_update_only_grows_and_spends_energy() {
+    let app = TestApp::spawn().await;
+    let (token, _) = app.login(5101).await;
+
+    let (status, body) = app.post("/game/update_score", Some(&token), json!({ "score": 120 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["score"], 120);
+    assert_eq!(body["energy"], app.config.energy_max - 120);
+    assert_eq!(body["unlocked_achievements"][0]["id"], "first_tap");
+
+    // Меньший счёт не уменьшает сохранённый
+    let (_, body) = app.post("/game/update_score", Some(&token), json!({ "score": 50 })).await;
+    assert_eq!(body["score"], 120);
+
+    let (status, _) = app.post("/game/update_score", Some(&token), json!({ "score": -1 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, state) = app.get("/game/state", Some(&token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state["score"], 120);
+
+    let (status, _) = app.post("/game/update_score", None, json!({ "score": 500 })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn leaderboard_orders_players_by_score() {
+    let app = TestApp::spawn().await;
+
+    for (telegram_id, score) in [(5201, 300), (5202, 900), (5203, 600)] {
+        let (token, _) = app.login(telegram_id).await;
+        let (status, _) = app.post("/game/update_score", Some(&token), json!({ "score": score })).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, body) = app.get("/game/leaderboard", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let scores: Vec<i64> = body.as_array().unwrap().iter().map(|e| e["score"].as_i64().unwrap()).collect();
+    assert_eq!(scores, [900, 600, 300]);
+    assert_eq!(body[0]["username"], "pilot5202");
+
+    let (status, _) = app.get("/game/leaderboard?league=Nowhere", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn claim_goes_from_pending_to_completed_once() {
+    let app = TestApp::spawn().await;
+    let (token, user_id) = app.login(5301).await;
+    let (other_token, _) = app.login(5302).await;
+
+    let (status, _) = app.post("/claim/start", Some(&token), json!({ "amount": "-1" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, created) = app.post("/claim/start", Some(&token), json!({ "amount": "25.50" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(created["status"], "pending");
+    let claim_id = created["claim_id"].as_str().unwrap();
+
+    // Чужую заявку подтвердить нельзя
+    let (status, _) = app.post("/claim/confirm", Some(&other_token), json!({ "claim_id": claim_id })).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, confirmed) = app.post("/claim/confirm", Some(&token), json!({ "claim_id": claim_id })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(confirmed["status"], "completed");
+    assert_eq!(confirmed["unlocked_achievements"][0]["id"], "first_withdrawal");
+
+    let (status, _) = app.post("/claim/confirm", Some(&token), json!({ "claim_id": claim_id })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let history: Vec<String> = sqlx::query_scalar(
+        "SELECT e.status FROM claim_status_events e JOIN claims c ON c.id = e.claim_id WHERE c.user_id = $1 ORDER BY e.id",
+    )
+    .bind(user_id)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(history, ["pending", "completed"]);
+}
+
+#[tokio::test]
+async fn claim_event_stream_ends_on_shutdown() {
+    let app = TestApp::spawn().await;
+    let (token, _) = app.login(5401).await;
+    let (_, created) = app.post("/claim/start", Some(&token), json!({ "amount": "10" })).await;
+    let uri = format!("/claim/{}/events", created["claim_id"].as_str().unwrap());
+
+    // Без остановки поток открыт бесконечно; после отмены токена ответ дочитывается до конца
+    let (streamed, _) = tokio::join!(
+        tokio::time::timeout(Duration::from_secs(5), app.get(&uri, Some(&token))),
+        async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            app.shutdown.cancel();
+        },
+    );
+    let (status, _) = streamed.expect("SSE stream must end after shutdown");
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// Тело SSE-ответа целиком; ждёт, пока сервер не закроет поток
+async fn read_stream(app: &TestApp, uri: &str, token: &str, last_event_id: Option<&str>) -> (StatusCode, String) {
+    let mut request = Request::get(uri).header("authorization", format!("Bearer {}", token));
+    if let Some(id) = last_event_id {
+        request = request.header("last-event-id", id);
+    }
+    let response = app.app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn claim_event_stream_ends_after_terminal_status() {
+    let app = TestApp::spawn().await;
+    app.listen_events().await;
+    let (token, _) = app.login(5402).await;
+    let (_, created) = app.post("/claim/start", Some(&token), json!({ "amount": "10" })).await;
+    let claim_id = created["claim_id"].as_str().unwrap();
+    let uri = format!("/claim/{}/events", claim_id);
+
+    // Поток открыт до подтверждения и закрывается сам после completed
+    let (streamed, _) = tokio::join!(
+        tokio::time::timeout(Duration::from_secs(5), read_stream(&app, &uri, &token, None)),
+        async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            app.post("/claim/confirm", Some(&token), json!({ "claim_id": claim_id })).await;
+        },
+    );
+    let (status, body) = streamed.expect("SSE stream must end after the terminal status");
+    assert_eq!(status, StatusCode::OK);
+    let statuses: Vec<&str> = ["pending", "completed"]
+        .into_iter()
+        .filter(|status| body.contains(&format!(r#""status":"{}""#, status)))
+        .collect();
+    assert_eq!(statuses, ["pending", "completed"]);
+
+    // Переподключение после финального статуса останавливает EventSource
+    let last_id = body
+        .lines()
+        .filter_map(|line| line.strip_prefix("id:"))
+        .next_back()
+        .unwrap()
+        .trim()
+        .to_string();
+    let (status, _) = read_stream(&app, &uri, &token, Some(&last_id)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // Без Last-Event-ID история отдаётся целиком, и поток тоже закрывается
+    let (status, body) = tokio::time::timeout(Duration::from_secs(5), read_stream(&app, &uri, &token, None))
+        .await
+        .expect("SSE stream of a completed claim must end");
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.matches("event: status").count(), 2);
+}
+
+async fn score(app: &TestApp, token: &str, score: i32) {
+    let (status, _) = app.post("/game/update_score", Some(token), json!({ "score": score })).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn clan_score(app: &TestApp, clan_id: &str) -> serde_json::Value {
+    app.get(&format!("/clans/{}", clan_id), None).await.1["score"].clone()
+}
+
+#[tokio::test]
+async fn clan_score_counts_points_earned_while_a_member_this_season() {
+    let app = TestApp::spawn().await;
+    let (owner, owner_id) = app.login(5501).await;
+    let (member, _) = app.login(5502).await;
+
+    // Очки, набранные до вступления, клану не идут
+    score(&app, &owner, 600).await;
+    let (_, created) = app.post("/clans", Some(&owner), json!({ "name": "Aliens" })).await;
+    let clan_id = created["clan_id"].as_str().unwrap().to_string();
+    assert_eq!(clan_score(&app, &clan_id).await, 0);
+    score(&app, &owner, 900).await;
+    assert_eq!(clan_score(&app, &clan_id).await, 300);
+
+    score(&app, &member, 500).await;
+    app.post(&format!("/clans/{}/join", clan_id), Some(&member), json!({})).await;
+    score(&app, &member, 700).await;
+    let (_, clan) = app.get(&format!("/clans/{}", clan_id), None).await;
+    assert_eq!(clan["score"], 500);
+    assert_eq!(clan["members"][0]["season_score"], 300);
+    assert_eq!(clan["members"][1]["season_score"], 200);
+
+    // Вышедший участник уносит только будущие очки
+    app.post("/clans/leave", Some(&member), json!({})).await;
+    score(&app, &member, 1000).await;
+    assert_eq!(clan_score(&app, &clan_id).await, 500);
+    let (_, top) = app.get("/clans/leaderboard", None).await;
+    assert_eq!(top[0]["score"], 500);
+    assert_eq!(top[0]["member_count"], 1);
+
+    // Членство с прошлого сезона продолжается записью нового сезона
+    sqlx::query("UPDATE clan_membership_history SET season = '2000-01' WHERE user_id = $1")
+        .bind(owner_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    score(&app, &owner, 1000).await;
+    assert_eq!(clan_score(&app, &clan_id).await, 300);
+    let (_, history) = app.get("/clans/history", Some(&owner)).await;
+    assert_eq!(history["history"].as_array().unwrap().len(), 2);
+    assert!(history["history"][1]["left_at"].is_string());
+    assert!(history["history"][0]["left_at"].is_null());
+}
+
+#[tokio::test]
+async fn tournament_credit_is_capped_by_energy() {
+    let app = TestApp::spawn().await;
+    let now = chrono::Utc::now();
+    let (status, tournament) = app
+        .admin_post(
+            "/admin/tournaments",
+            json!({ "title": "Cup", "starts_at": now - chrono::Duration::minutes(1), "ends_at": now + chrono::Duration::hours(1) }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let tournament_id = tournament["id"].as_str().unwrap();
+    let (token, user_id) = app.login(5601).await;
+    let (status, _) = app.post(&format!("/tournaments/{}/join", tournament_id), Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Заявленный клиентом счёт принимается только в пределах энергии
+    let max = app.config.energy_max;
+    score(&app, &token, max * 5).await;
+    let entry: i64 = sqlx::query_scalar("SELECT score FROM tournament_entries WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(entry, i64::from(max));
+
+    // Энергия потрачена: дальнейший прирост не засчитывается
+    score(&app, &token, max * 6).await;
+    let (_, state) = app.get("/game/state", Some(&token)).await;
+    assert!(state["score"].as_i64().unwrap() <= i64::from(max) + 5, "score {} is not energy-backed", state["score"]);
+    let entry: i64 = sqlx::query_scalar("SELECT score FROM tournament_entries WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert!(entry <= i64::from(max) + 5, "only regenerated energy may be credited, got {}", entry);
+}
+
+#[tokio::test]
+async fn inflated_total_earns_no_rewards_beyond_energy() {
+    let app = TestApp::spawn().await;
+    let (token, user_id) = app.login(5602).await;
+
+    // 60 000 очков сразу дали бы Gold и Platinum и достижение score_10k
+    let max = app.config.energy_max;
+    let (status, body) = app.post("/game/update_score", Some(&token), json!({ "score": 60_000 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["score"], max);
+    let league = &app.config.leagues[leagues::league_index(&app.config, max)];
+    assert_eq!(body["league"]["league"], league.name.as_str());
+    let unlocked: Vec<&str> = body["unlocked_achievements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["id"].as_str().unwrap())
+        .collect();
+    assert!(!unlocked.contains(&"score_10k"), "unlocked {:?}", unlocked);
+
+    let promoted: Vec<String> = sqlx::query_scalar("SELECT league FROM league_promotions WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    assert!(promoted.iter().all(|name| name == &league.name), "promoted to {:?}", promoted);
+    let earned: Decimal = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(amount), 0) FROM balance_transactions WHERE user_id = $1 AND kind = 'score_earning'",
+    )
+    .bind(user_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(earned, Decimal::from(max));
+}
+
+#[tokio::test]
+async fn readiness_reports_components_and_fails_while_draining() {
+    let app = TestApp::spawn().await;
+
+    let (status, body) = app.get("/health/ready", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["checks"]["database"]["status"], "ok");
+    assert!(body["checks"]["database"]["latency_ms"].is_u64());
+    assert_eq!(body["checks"]["migrations"]["pending"], 0);
+
+    // Зарегистрированная задача без пульса делает инстанс неготовым
+    app.health.register_periodic(health::TOURNAMENT_PAYOUTS, Duration::from_secs(60));
+    let (status, body) = app.get("/health/ready", None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["checks"]["tournament_payouts"]["status"], "fail");
+
+    app.health.beat(health::TOURNAMENT_PAYOUTS);
+    let (status, _) = app.get("/health/ready", None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Схема новее бинарника
+    sqlx::query(
+        "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) \
+         VALUES (29991231000000, 'from the future', true, '\\x00', 0)",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    // При rolling deploy старые поды остаются готовыми, отказ — только при старте
+    let (status, body) = app.get("/health/ready", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["checks"]["migrations"]["status"], "ok");
+    assert_eq!(body["checks"]["migrations"]["unknown"], json!([29991231000000i64]));
+    assert!(db::ensure_schema_compatible(&app.pool).await.is_err());
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = 29991231000000")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    // Неприменённая миграция снимает инстанс с трафика
+    let mark_latest = "UPDATE _sqlx_migrations SET success = $1 WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)";
+    sqlx::query(mark_latest).bind(false).execute(&app.pool).await.unwrap();
+    let (status, body) = app.get("/health/ready", None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["checks"]["migrations"]["status"], "fail");
+    assert_eq!(body["checks"]["migrations"]["pending"], 1);
+    sqlx::query(mark_latest).bind(true).execute(&app.pool).await.unwrap();
+
+    // Текст ошибки базы наружу не отдаётся
+    sqlx::query("ALTER TABLE _sqlx_migrations RENAME COLUMN success TO success_hidden").execute(&app.pool).await.unwrap();
+    let (status, body) = app.get("/health/ready", None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["checks"]["migrations"]["error"], "unavailable");
+    sqlx::query("ALTER TABLE _sqlx_migrations RENAME COLUMN success_hidden TO success").execute(&app.pool).await.unwrap();
+
+    app.health.start_draining();
+    let (status, body) = app.get("/health/ready", None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "draining");
+
+    // Liveness от зависимостей и остановки не зависит
+    let (status, _) = app.get("/health/live", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn metrics_are_labelled_by_route_template_and_outcome() {
+    let handle = metrics_handle();
+    let app = TestApp::spawn().await;
+    let (token, _) = app.login(5501).await;
+
+    let forged = mint_init_data("999:wrong-bot", &telegram_user(5501), &[]);
+    let (status, _) = app.post("/auth/telegram", None, json!({ "initData": forged })).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app.post("/game/update_score", Some(&token), json!({ "score": 10 })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, created) = app.post("/claim/start", Some(&token), json!({ "amount": "5" })).await;
+    let claim_id = created["claim_id"].as_str().unwrap();
+    let (status, _) = app.get(&format!("/claim/{}/events", Uuid::nil()), Some(&token)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let metrics_app = alien_tap_backend::routes::metrics::router(handle, app.pool.clone());
+    let response = metrics_app
+        .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+
+    for expected in [
+        r#"http_requests_total{method="POST",route="/game/update_score",status="200"}"#,
+        r#"http_requests_total{method="GET",route="/claim/:id/events",status="404"}"#,
+        r#"auth_attempts_total{result="failure",reason="invalid_signature"}"#,
+        r#"auth_attempts_total{result="success",reason="none"}"#,
+        r#"taps_total{source="http",result="accepted"}"#,
+        r#"claims_total{status="pending"}"#,
+        "db_pool_max_connections 5",
+    ] {
+        assert!(body.contains(expected), "missing {} in:\n{}", expected, body);
+    }
+    // Идентификаторы не попадают в метки
+    assert!(!body.contains(claim_id) && !body.contains(&Uuid::nil().to_string()));
+}
+
+/// Буфер, в который пишет тестовый subscriber
+#[derive(Clone, Default)]
+struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn query_token_is_not_logged() {
+    let app = TestApp::spawn().await;
+    let (token, _) = app.login(5402).await;
+
+    let logs = LogBuffer::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let uri = format!("/claim/{}/events?token={}", Uuid::new_v4(), token);
+    let response = app.app.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    assert!(logs.contains("token=***"), "request span is missing from logs: {}", logs);
+    assert!(!logs.contains(&token), "JWT leaked into logs: {}", logs);
+}
+
+#[tokio::test]
+async fn request_id_is_echoed_in_headers_and_error_bodies() {
+    let app = TestApp::spawn().await;
+
+    let request = Request::get("/me").header("x-request-id", "client-req-42").body(Body::empty()).unwrap();
+    let response = app.app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["x-request-id"], "client-req-42");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["request_id"], "client-req-42");
+
+    // Без заголовка или с недопустимым значением id генерируется сервером
+    let request = Request::get("/health").header("x-request-id", "not valid").body(Body::empty()).unwrap();
+    let response = app.app.clone().oneshot(request).await.unwrap();
+    let generated = response.headers()["x-request-id"].to_str().unwrap();
+    assert!(generated.parse::<Uuid>().is_ok(), "unexpected request id {}", generated);
+}
+
+#[tokio::test]
+async fn failed_referral_attribution_rolls_back_first_login() {
+    let app = TestApp::spawn().await;
+    let (_, referrer_id) = app.login(5601).await;
+    let code: String = sqlx::query_scalar("SELECT referral_code FROM users WHERE id = $1")
+        .bind(referrer_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO pending_referrals (telegram_id, start_param) VALUES (5602, $1)")
+        .bind(format!("ref_{}", code))
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    // Привязка падает посреди транзакции входа
+    sqlx::raw_sql(
+        "CREATE FUNCTION fail_referral() RETURNS trigger AS $$ BEGIN RAISE EXCEPTION 'boom'; END $$ LANGUAGE plpgsql;
+         CREATE TRIGGER fail_referral BEFORE UPDATE OF referred_by ON users FOR EACH ROW EXECUTE FUNCTION fail_referral();",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    let init_data = mint_init_data(BOT_TOKEN, &telegram_user(5602), &[]);
+    let (status, _) = app.post("/auth/telegram", None, json!({ "initData": init_data })).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE telegram_id = 5602")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pending_referrals WHERE telegram_id = 5602")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!((users, pending), (0, 1));
+
+    // Повторный вход снова первый: привязка и бонусы не теряются
+    sqlx::query("DROP TRIGGER fail_referral ON users").execute(&app.pool).await.unwrap();
+    let (_, user_id) = app.login(5602).await;
+    let referred_by: Option<Uuid> = sqlx::query_scalar("SELECT referred_by FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(referred_by, Some(referrer_id));
+}
+
+#[tokio::test]
+async fn concurrent_confirms_complete_claim_once() {
+    let app = TestApp::spawn().await;
+    let (token, user_id) = app.login(5701).await;
+    let (_, created) = app.post("/claim/start", Some(&token), json!({ "amount": "7" })).await;
+    let body = json!({ "claim_id": created["claim_id"] });
+
+    let confirms = futures_util::future::join_all(
+        (0..5).map(|_| app.post("/claim/confirm", Some(&token), body.clone())),
+    )
+    .await;
+    let mut statuses: Vec<StatusCode> = confirms.into_iter().map(|(status, _)| status).collect();
+    statuses.sort();
+    assert_eq!(statuses[0], StatusCode::OK);
+    assert!(statuses[1..].iter().all(|status| *status == StatusCode::BAD_REQUEST), "{:?}", statuses);
+
+    let completed: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM claim_status_events e JOIN claims c ON c.id = e.claim_id \
+         WHERE c.user_id = $1 AND e.status = 'completed'",
+    )
+    .bind(user_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    let notified: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND kind = 'claim_completed'")
+        .bind(user_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!((completed, notified), (1, 1));
+}
@@ -0,0 +1,380 @@
+//! Webhook бота, платежи Stars, уведомления и проверка заданий против мока Telegram Bot API
+
+mod common;
+
+use std::time::Duration;
+
+use alien_tap_backend::services::health::Health;
+use alien_tap_backend::services::notifications;
+use alien_tap_backend::utils::bot_api::BotApi;
+use axum::http::StatusCode;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use common::bot_api::MockBotApi;
+use common::TestApp;
+
+const SUCCESSFUL_PAYMENT: &str = include_str!("fixtures/telegram/successful_payment.json");
+const START_REFERRAL: &str = include_str!("fixtures/telegram/start_referral.json");
+const STATS: &str = include_str!("fixtures/telegram/stats.json");
+const GROUP_LEADERBOARD: &str = include_str!("fixtures/telegram/group_leaderboard.json");
+
+fn fixture(json: &str) -> Value {
+    serde_json::from_str(json).unwrap()
+}
+
+async fn balance(app: &TestApp, user_id: Uuid) -> Decimal {
+    sqlx::query_scalar("SELECT COALESCE((SELECT balance FROM balances WHERE user_id = $1), 0)")
+        .bind(user_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+async fn order_status(app: &TestApp, order_id: &str) -> String {
+    sqlx::query_scalar("SELECT status FROM star_orders WHERE id = $1::UUID")
+        .bind(order_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+/// successful_payment из фикстуры для заказа и покупателя
+fn payment(order_id: &str, telegram_id: i64, total_amount: i64) -> Value {
+    let mut update = fixture(SUCCESSFUL_PAYMENT);
+    update["message"]["from"]["id"] = telegram_id.into();
+    update["message"]["chat"]["id"] = telegram_id.into();
+    let payment = &mut update["message"]["successful_payment"];
+    payment["invoice_payload"] = order_id.into();
+    payment["total_amount"] = total_amount.into();
+    payment["telegram_payment_charge_id"] = format!("stx-{}", order_id).into();
+    update
+}
+
+#[tokio::test]
+async fn invoice_is_created_through_bot_api_and_payment_credits_once() {
+    let bot_api = MockBotApi::start().await;
+    let app = TestApp::spawn_with_bot_api(&bot_api.url).await;
+    let (token, user_id) = app.login(424242).await;
+
+    let (status, invoice) = app.post("/shop/coins_10k/invoice", Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(invoice["invoice_link"], "https://t.me/$mock-invoice");
+    let order_id = invoice["order_id"].as_str().unwrap().to_string();
+
+    let created = bot_api.calls("createInvoiceLink");
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0]["payload"], order_id);
+    assert_eq!(created[0]["currency"], "XTR");
+    assert_eq!(created[0]["prices"][0]["amount"], 50);
+
+    // Telegram повторяет доставку, пока не получит 2xx: покупка выдаётся один раз
+    let before = balance(&app, user_id).await;
+    let (status, reply) = app.webhook(payment(&order_id, 424242, 50)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reply["method"], "sendMessage");
+    let (status, reply) = app.webhook(payment(&order_id, 424242, 50)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reply, Value::Null);
+
+    assert_eq!(balance(&app, user_id).await - before, Decimal::from(10_000));
+    assert_eq!(order_status(&app, &order_id).await, "paid");
+}
+
+#[tokio::test]
+async fn payment_with_wrong_amount_is_not_fulfilled() {
+    let bot_api = MockBotApi::start().await;
+    let app = TestApp::spawn_with_bot_api(&bot_api.url).await;
+    let (token, user_id) = app.login(424243).await;
+    let (_, invoice) = app.post("/shop/coins_10k/invoice", Some(&token), json!({})).await;
+    let order_id = invoice["order_id"].as_str().unwrap();
+
+    let before = balance(&app, user_id).await;
+    let (status, _) = app.webhook(payment(order_id, 424243, 49)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    assert_eq!(balance(&app, user_id).await, before);
+    assert_eq!(order_status(&app, order_id).await, "created");
+}
+
+#[tokio::test]
+async fn refund_goes_through_bot_api_and_tolerates_already_refunded_charge() {
+    let bot_api = MockBotApi::start().await;
+    let app = TestApp::spawn_with_bot_api(&bot_api.url).await;
+    let (token, user_id) = app.login(424244).await;
+    let (_, invoice) = app.post("/shop/coins_10k/invoice", Some(&token), json!({})).await;
+    let order_id = invoice["order_id"].as_str().unwrap();
+    app.webhook(payment(order_id, 424244, 50)).await;
+    let paid = balance(&app, user_id).await;
+    let refund_uri = format!("/admin/orders/{}/refund", order_id);
+
+    // Ошибка Bot API: заказ остаётся оплаченным, монеты не списываются
+    bot_api.fail("refundStarPayment", 400, "Bad Request: PAYMENT_NOT_FOUND", None);
+    let (status, _) = app.admin_post(&refund_uri, json!({})).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(order_status(&app, order_id).await, "paid");
+
+    // Звёзды уже вернули (например, из прошлой попытки): возврат фиксируется у нас
+    bot_api.fail("refundStarPayment", 400, "Bad Request: CHARGE_ALREADY_REFUNDED", None);
+    let (status, body) = app.admin_post(&refund_uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["reverted"], true);
+    assert_eq!(order_status(&app, order_id).await, "refunded");
+    assert_eq!(paid - balance(&app, user_id).await, Decimal::from(10_000));
+
+    let calls = bot_api.calls("refundStarPayment");
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[1]["user_id"], 424244);
+    assert_eq!(calls[1]["telegram_payment_charge_id"], format!("stx-{}", order_id));
+
+    // Повторный возврат не доходит до Bot API и ничего не списывает
+    let (status, _) = app.admin_post(&refund_uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(bot_api.calls("refundStarPayment").len(), 2);
+}
+
+#[tokio::test]
+async fn concurrent_refunds_debit_once() {
+    let bot_api = MockBotApi::start().await;
+    let app = TestApp::spawn_with_bot_api(&bot_api.url).await;
+    let (token, user_id) = app.login(424245).await;
+    let (_, invoice) = app.post("/shop/coins_10k/invoice", Some(&token), json!({})).await;
+    let order_id = invoice["order_id"].as_str().unwrap();
+    app.webhook(payment(order_id, 424245, 50)).await;
+    let paid = balance(&app, user_id).await;
+
+    // Второй вызов Telegram отклоняет как уже возвращённый — раньше это списывало монеты дважды
+    bot_api.reply("refundStarPayment", json!({ "ok": true, "result": true }));
+    bot_api.fail("refundStarPayment", 400, "Bad Request: CHARGE_ALREADY_REFUNDED", None);
+    let refund_uri = format!("/admin/orders/{}/refund", order_id);
+    let (first, second) = tokio::join!(
+        app.admin_post(&refund_uri, json!({})),
+        app.admin_post(&refund_uri, json!({})),
+    );
+
+    let mut statuses = [first.0, second.0];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::BAD_REQUEST]);
+    assert_eq!(paid - balance(&app, user_id).await, Decimal::from(10_000));
+}
+
+/// Игрок с уведомлением в outbox; возвращает id уведомления
+async fn queue_notification(app: &TestApp, telegram_id: i64, allows_write_to_pm: bool) -> (Uuid, i64) {
+    let (_, user_id) = app.login(telegram_id).await;
+    sqlx::query("UPDATE users SET allows_write_to_pm = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(allows_write_to_pm)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let id = sqlx::query_scalar(
+        "INSERT INTO notifications (user_id, kind, dedupe_key, text) VALUES ($1, 'test', 'once', 'Hello') RETURNING id",
+    )
+    .bind(user_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    (user_id, id)
+}
+
+/// Запускает воркер outbox и ждёт, пока условие над базой не выполнится
+async fn deliver_until(app: &TestApp, done: &str) {
+    let shutdown = CancellationToken::new();
+    tokio::spawn(notifications::run_outbox_worker(
+        app.pool.clone(),
+        app.config.clone(),
+        BotApi::from_config(&app.config),
+        Health::new(),
+        shutdown.clone(),
+    ));
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while !sqlx::query_scalar::<_, bool>(done).fetch_one(&app.pool).await.unwrap() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("outbox worker did not get there in 10s");
+    shutdown.cancel();
+}
+
+#[tokio::test]
+async fn flood_control_pauses_delivery_and_reschedules_the_rest_of_the_batch() {
+    let bot_api = MockBotApi::start().await;
+    let app = TestApp::spawn_with_bot_api(&bot_api.url).await;
+    for telegram_id in [424251, 424252, 424253] {
+        queue_notification(&app, telegram_id, true).await;
+    }
+
+    bot_api.fail("sendMessage", 429, "Too Many Requests: retry after 30", Some(30));
+    // Аренда ставит next_attempt_at на минуту вперёд, retry_after — на 30 секунд
+    deliver_until(
+        &app,
+        "SELECT COUNT(*) = 3 FROM notifications \
+         WHERE next_attempt_at BETWEEN now() + interval '20 seconds' AND now() + interval '45 seconds'",
+    )
+    .await;
+
+    // Остальные два сообщения даже не пытались отправить
+    assert_eq!(bot_api.calls("sendMessage").len(), 1);
+    let rows: Vec<(String, i32, Option<String>)> =
+        sqlx::query_as("SELECT status, attempts, last_error FROM notifications ORDER BY id")
+            .fetch_all(&app.pool)
+            .await
+            .unwrap();
+    for (status, attempts, _) in &rows {
+        assert_eq!(status, "pending");
+        assert_eq!(*attempts, 0, "flood control does not count as an attempt");
+    }
+    assert_eq!(rows[0].2.as_deref(), Some("Too Many Requests: retry after 30"));
+    assert_eq!(rows[1].2, None);
+}
+
+#[tokio::test]
+async fn transport_errors_are_retried_and_stored_without_the_bot_token() {
+    let bot_api = MockBotApi::start().await;
+    let app = TestApp::spawn_with_bot_api(&bot_api.url).await;
+    let (_, id) = queue_notification(&app, 424256, true).await;
+
+    bot_api.hang("sendMessage");
+    deliver_until(&app, "SELECT attempts = 1 FROM notifications").await;
+
+    let (status, last_error): (String, Option<String>) =
+        sqlx::query_as("SELECT status, last_error FROM notifications WHERE id = $1")
+            .bind(id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "pending");
+    assert_eq!(last_error.as_deref(), Some("timeout"));
+}
+
+#[tokio::test]
+async fn blocked_and_opted_out_players_are_not_messaged() {
+    let bot_api = MockBotApi::start().await;
+    let app = TestApp::spawn_with_bot_api(&bot_api.url).await;
+    let (blocked, _) = queue_notification(&app, 424254, true).await;
+    queue_notification(&app, 424255, false).await;
+
+    bot_api.fail("sendMessage", 403, "Forbidden: bot was blocked by the user", None);
+    deliver_until(&app, "SELECT COUNT(*) = 0 FROM notifications WHERE status = 'pending'").await;
+
+    let statuses: Vec<String> = sqlx::query_scalar("SELECT status FROM notifications ORDER BY id")
+        .fetch_all(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(statuses, ["failed", "skipped"]);
+    let allows: bool = sqlx::query_scalar("SELECT allows_write_to_pm FROM users WHERE id = $1")
+        .bind(blocked)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert!(!allows, "403 must stop further messages to the player");
+
+    // Отказавшемуся от сообщений игроку запрос не отправлялся
+    let sent = bot_api.calls("sendMessage");
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0]["chat_id"], 424254);
+}
+
+#[tokio::test]
+async fn channel_subscription_is_verified_through_bot_api_with_a_timeout() {
+    let bot_api = MockBotApi::start().await;
+    let app = TestApp::spawn_with_bot_api(&bot_api.url).await;
+    let (token, _) = app.login(424261).await;
+    sqlx::query(
+        "INSERT INTO tasks (id, kind, title, reward, params) \
+         VALUES ('join_news', 'join_channel', 'Join news', 500, '{\"chat_id\": \"@alien_news\"}')",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    bot_api.reply("getChatMember", json!({ "ok": true, "result": { "status": "left" } }));
+    let (status, body) = app.post("/tasks/join_news/verify", Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["completed"], false);
+    assert_eq!(body["message"], "Join @alien_news first");
+    let calls = bot_api.calls("getChatMember");
+    assert_eq!(calls[0]["chat_id"], "@alien_news");
+    assert_eq!(calls[0]["user_id"], 424261);
+
+    // Зависший Telegram не держит запрос дольше TELEGRAM_API_TIMEOUT_MS
+    bot_api.hang("getChatMember");
+    let started = std::time::Instant::now();
+    let (status, _) = app.post("/tasks/join_news/verify", Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let (status, body) = app.post("/tasks/join_news/verify", Some(&token), json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["completed"], true);
+    assert_eq!(bot_api.calls("getChatMember").len(), 3);
+}
+
+#[tokio::test]
+async fn start_with_referral_code_is_attributed_on_first_login() {
+    let app = TestApp::spawn().await;
+    let (_, referrer) = app.login(515150).await;
+    sqlx::query("UPDATE users SET referral_code = 'ABC123' WHERE id = $1")
+        .bind(referrer)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, reply) = app.webhook(fixture(START_REFERRAL)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reply["method"], "sendMessage");
+    assert_eq!(reply["chat_id"], 515151);
+    let pending: Option<String> = sqlx::query_scalar("SELECT start_param FROM pending_referrals WHERE telegram_id = 515151")
+        .fetch_optional(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(pending.as_deref(), Some("ref_ABC123"));
+
+    // Mini App открыли без startapp: код берётся из /start
+    let (_, referee) = app.login(515151).await;
+    let referred_by: Option<Uuid> = sqlx::query_scalar("SELECT referred_by FROM users WHERE id = $1")
+        .bind(referee)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(referred_by, Some(referrer));
+    let left: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pending_referrals")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(left, 0);
+}
+
+#[tokio::test]
+async fn stats_and_leaderboard_commands_read_the_database() {
+    let app = TestApp::spawn().await;
+
+    let (status, reply) = app.webhook(fixture(STATS)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(reply["text"].as_str().unwrap().starts_with("You haven't played yet"));
+
+    for (telegram_id, score) in [(424242, 300), (424243, 500)] {
+        let (token, _) = app.login(telegram_id).await;
+        let (status, _) = app.post("/game/update_score", Some(&token), json!({ "score": score })).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, reply) = app.webhook(fixture(STATS)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reply["chat_id"], 424242);
+    let text = reply["text"].as_str().unwrap();
+    assert!(text.contains("Score: 300"), "{}", text);
+    assert!(text.contains("Rank: #2"), "{}", text);
+
+    // В группе ответ уходит в чат группы
+    let (status, reply) = app.webhook(fixture(GROUP_LEADERBOARD)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(reply["chat_id"], -1001234567890i64);
+    assert_eq!(
+        reply["text"],
+        "🏆 <b>Top players</b>\n1. @pilot424243 — 500\n2. @pilot424242 — 300"
+    );
+}
@@ -0,0 +1,101 @@
+//! Мок Telegram Bot API: запоминает вызовы и отвечает заранее заданными ответами.
+//! Без заданного ответа метод отвечает успехом с правдоподобным `result`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Path, State};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+
+enum Reply {
+    Json(Value),
+    /// Запрос повисает без ответа (проверка таймаутов клиента)
+    Hang,
+}
+
+#[derive(Default)]
+struct Mock {
+    calls: Vec<(String, Value)>,
+    replies: HashMap<String, VecDeque<Reply>>,
+}
+
+#[derive(Clone)]
+pub struct MockBotApi {
+    pub url: String,
+    mock: Arc<Mutex<Mock>>,
+}
+
+impl MockBotApi {
+    pub async fn start() -> Self {
+        let mock = Arc::new(Mutex::new(Mock::default()));
+        let app = Router::new()
+            .route("/:token/:method", post(handle))
+            .with_state(mock.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        MockBotApi { url, mock }
+    }
+
+    /// Следующий вызов метода получит этот ответ целиком (`{"ok": ..., ...}`)
+    pub fn reply(&self, method: &str, body: Value) {
+        self.push(method, Reply::Json(body));
+    }
+
+    /// Ошибка Bot API, как её возвращает Telegram
+    pub fn fail(&self, method: &str, code: i64, description: &str, retry_after: Option<u64>) {
+        let mut body = json!({ "ok": false, "error_code": code, "description": description });
+        if let Some(retry_after) = retry_after {
+            body["parameters"] = json!({ "retry_after": retry_after });
+        }
+        self.reply(method, body);
+    }
+
+    pub fn hang(&self, method: &str) {
+        self.push(method, Reply::Hang);
+    }
+
+    /// Параметры всех вызовов метода по порядку
+    pub fn calls(&self, method: &str) -> Vec<Value> {
+        self.mock
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter(|(name, _)| name == method)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+
+    fn push(&self, method: &str, reply: Reply) {
+        self.mock.lock().unwrap().replies.entry(method.to_string()).or_default().push_back(reply);
+    }
+}
+
+async fn handle(
+    State(mock): State<Arc<Mutex<Mock>>>,
+    Path((_token, method)): Path<(String, String)>,
+    Json(params): Json<Value>,
+) -> Json<Value> {
+    let reply = {
+        let mut mock = mock.lock().unwrap();
+        mock.calls.push((method.clone(), params));
+        mock.replies.get_mut(&method).and_then(VecDeque::pop_front)
+    };
+    match reply {
+        Some(Reply::Json(body)) => Json(body),
+        Some(Reply::Hang) => std::future::pending().await,
+        None => Json(json!({ "ok": true, "result": default_result(&method) })),
+    }
+}
+
+fn default_result(method: &str) -> Value {
+    match method {
+        "sendMessage" => json!({ "message_id": 1 }),
+        "createInvoiceLink" => json!("https://t.me/$mock-invoice"),
+        "getChatMember" => json!({ "status": "member" }),
+        _ => json!(true),
+    }
+}
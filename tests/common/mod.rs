@@ -0,0 +1,234 @@
+//! Общая обвязка интеграционных тестов: отдельная база Postgres на каждый тест,
+//! полный роутер из `build_app` и подписанный initData для тестового бота.
+
+#![allow(dead_code)]
+
+pub mod bot_api;
+
+use std::str::FromStr;
+use std::sync::{Arc, Once, OnceLock};
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use metrics_exporter_prometheus::PrometheusHandle;
+use serde_json::{json, Value};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{ConnectOptions, Executor, PgPool};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use alien_tap_backend::app_state::AppState;
+use alien_tap_backend::build_app;
+use alien_tap_backend::config::Config;
+use alien_tap_backend::db::MIGRATOR;
+use alien_tap_backend::repos::pg::PgRepo;
+use alien_tap_backend::services::events::EventBus;
+use alien_tap_backend::services::health::{self, Health};
+use alien_tap_backend::services::realtime::WsSessions;
+use alien_tap_backend::utils::bot_api::BotApi;
+use alien_tap_backend::utils::telegram;
+
+pub const BOT_TOKEN: &str = "123456:TEST-bot-token";
+pub const JWT_SECRET: &str = "integration-secret";
+pub const WEBHOOK_SECRET: &str = "integration-webhook-secret";
+pub const ADMIN_TOKEN: &str = "integration-admin-token";
+
+/// initData игрока со свежим auth_date, подписанный как в Telegram
+pub fn mint_init_data(bot_token: &str, user: &Value, extra: &[(&str, &str)]) -> String {
+    let user = user.to_string();
+    let auth_date = chrono::Utc::now().timestamp().to_string();
+    let mut params: Vec<(&str, &str)> = vec![("auth_date", &auth_date), ("query_id", "AAHdF6IQAAAAAN0XohDhrOrc"), ("user", &user)];
+    params.extend_from_slice(extra);
+    telegram::sign_init_data(&params, bot_token)
+}
+
+/// Recorder глобальный: ставится один раз на весь тестовый бинарник, счётчики общие для всех тестов
+pub fn metrics_handle() -> PrometheusHandle {
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+    HANDLE
+        .get_or_init(|| alien_tap_backend::services::metrics::install().unwrap())
+        .clone()
+}
+
+pub fn telegram_user(telegram_id: i64) -> Value {
+    json!({ "id": telegram_id, "first_name": format!("Pilot{}", telegram_id), "username": format!("pilot{}", telegram_id) })
+}
+
+pub struct TestApp {
+    pub app: Router,
+    pub pool: PgPool,
+    pub config: Config,
+    pub health: Health,
+    pub shutdown: CancellationToken,
+    pub events: EventBus,
+    admin: PgConnectOptions,
+    database: String,
+}
+
+impl TestApp {
+    /// Создаёт пустую базу рядом с `TEST_DATABASE_URL` (или `DATABASE_URL`) и применяет миграции.
+    /// Bot API недоступен: уведомления только ставятся в outbox
+    pub async fn spawn() -> Self {
+        Self::spawn_with_bot_api("http://127.0.0.1:9").await
+    }
+
+    /// То же, но запросы к Bot API уходят на мок (`MockBotApi::url`)
+    pub async fn spawn_with_bot_api(bot_api_url: &str) -> Self {
+        // .env загружается в окружение один раз: set_var из параллельных тестов — гонка
+        static DOTENV: Once = Once::new();
+        DOTENV.call_once(|| {
+            dotenvy::dotenv().ok();
+        });
+        let url = std::env::var("TEST_DATABASE_URL")
+            .or_else(|_| std::env::var("DATABASE_URL"))
+            .expect("Integration tests need TEST_DATABASE_URL or DATABASE_URL pointing to a local Postgres");
+        let admin = PgConnectOptions::from_str(&url).unwrap().disable_statement_logging();
+
+        let database = format!("alien_test_{}", Uuid::new_v4().simple());
+        let mut conn = admin.connect().await.expect("Failed to connect to test Postgres");
+        conn.execute(format!(r#"CREATE DATABASE "{}""#, database).as_str()).await.unwrap();
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect_with(admin.clone().database(&database))
+            .await
+            .unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+
+        // Остальные параметры — значения по умолчанию, независимо от окружения разработчика
+        let config = Config::from_vars(|key| match key {
+            "DATABASE_URL" => Ok(url.clone()),
+            "TELEGRAM_BOT_TOKEN" => Ok(BOT_TOKEN.to_string()),
+            "JWT_SECRET" => Ok(JWT_SECRET.to_string()),
+            "TELEGRAM_API_BASE_URL" => Ok(bot_api_url.to_string()),
+            "TELEGRAM_API_TIMEOUT_MS" => Ok("500".to_string()),
+            "TELEGRAM_WEBHOOK_SECRET" => Ok(WEBHOOK_SECRET.to_string()),
+            "ADMIN_TOKEN" => Ok(ADMIN_TOKEN.to_string()),
+            _ => Err(std::env::VarError::NotPresent),
+        })
+        .unwrap();
+
+        let health = Health::new();
+        let shutdown = CancellationToken::new();
+        let events = EventBus::new();
+        let repo = Arc::new(PgRepo::new(pool.clone()));
+        let state = AppState {
+            pool: pool.clone(),
+            bot_api: BotApi::from_config(&config),
+            ws_sessions: WsSessions::new(std::time::Duration::from_secs(1)),
+            events: events.clone(),
+            health: health.clone(),
+            shutdown: shutdown.clone(),
+            users: repo.clone(),
+            scores: repo.clone(),
+            claims: repo,
+            config: config.clone(),
+        };
+
+        TestApp {
+            app: build_app(state),
+            pool,
+            config,
+            health,
+            shutdown,
+            events,
+            admin,
+            database,
+        }
+    }
+
+    pub async fn request(&self, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+        let headers = token.map(|token| ("authorization", format!("Bearer {}", token)));
+        self.request_with_headers(method, uri, headers.as_slice(), body).await
+    }
+
+    pub async fn request_with_headers(
+        &self,
+        method: Method,
+        uri: &str,
+        headers: &[(&str, String)],
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let request = match body {
+            Some(body) => request
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    pub async fn get(&self, uri: &str, token: Option<&str>) -> (StatusCode, Value) {
+        self.request(Method::GET, uri, token, None).await
+    }
+
+    pub async fn post(&self, uri: &str, token: Option<&str>, body: Value) -> (StatusCode, Value) {
+        self.request(Method::POST, uri, token, Some(body)).await
+    }
+
+    /// Доставляет обновление в webhook бота так же, как Telegram
+    pub async fn webhook(&self, update: Value) -> (StatusCode, Value) {
+        let secret = [("x-telegram-bot-api-secret-token", WEBHOOK_SECRET.to_string())];
+        self.request_with_headers(Method::POST, "/bot/webhook", &secret, Some(update)).await
+    }
+
+    pub async fn admin_post(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        let token = [("x-admin-token", ADMIN_TOKEN.to_string())];
+        self.request_with_headers(Method::POST, uri, &token, Some(body)).await
+    }
+
+    /// Запускает LISTEN шины событий, как на сервере, и ждёт подключения:
+    /// без него события из базы не доходят до SSE и WebSocket
+    pub async fn listen_events(&self) {
+        let health = Health::new();
+        tokio::spawn(self.events.clone().run_listener(self.pool.clone(), health.clone(), self.shutdown.clone()));
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while !health.workers().iter().any(|(name, status)| *name == health::EVENT_LISTENER && status.healthy) {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("event listener did not connect");
+    }
+
+    /// Входит через /auth/telegram с подписанным initData; возвращает JWT и id игрока
+    pub async fn login(&self, telegram_id: i64) -> (String, Uuid) {
+        let init_data = mint_init_data(BOT_TOKEN, &telegram_user(telegram_id), &[]);
+        let (status, body) = self.post("/auth/telegram", None, json!({ "initData": init_data })).await;
+        assert_eq!(status, StatusCode::OK, "login failed: {}", body);
+        (
+            body["token"].as_str().unwrap().to_string(),
+            body["user_id"].as_str().unwrap().parse().unwrap(),
+        )
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        // Drop синхронный, а рантайм теста уже завершается: удаляем базу в отдельном потоке
+        let admin = self.admin.clone();
+        let database = self.database.clone();
+        let _ = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            runtime.block_on(async {
+                if let Ok(mut conn) = admin.connect().await {
+                    let _ = conn
+                        .execute(format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, database).as_str())
+                        .await;
+                }
+            });
+        })
+        .join();
+    }
+}
@@ -0,0 +1,66 @@
+//! Шина событий между инстансами поверх Postgres LISTEN/NOTIFY
+
+mod common;
+
+use std::time::Duration;
+
+use alien_tap_backend::models::event::AppEvent;
+use alien_tap_backend::services::events::{BusMessage, EventBus};
+use alien_tap_backend::services::health::{self, Health};
+use axum::http::StatusCode;
+use serde_json::json;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use common::TestApp;
+
+/// Инстанс с собственной шиной, слушающей ту же базу
+async fn replica(app: &TestApp, shutdown: &CancellationToken) -> broadcast::Receiver<BusMessage> {
+    let bus = EventBus::new();
+    let subscriber = bus.subscribe();
+    let health = Health::new();
+    tokio::spawn(bus.run_listener(app.pool.clone(), health.clone(), shutdown.clone()));
+
+    // LISTEN должен быть установлен до публикации: NOTIFY не хранится
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !health.workers().iter().any(|(name, status)| *name == health::EVENT_LISTENER && status.healthy) {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("event listener did not connect");
+    subscriber
+}
+
+async fn next_score_change(subscriber: &mut broadcast::Receiver<BusMessage>) -> (uuid::Uuid, i32, i32) {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), subscriber.recv())
+            .await
+            .expect("no event within 5s")
+            .unwrap();
+        if let BusMessage::Event(AppEvent::ScoreChanged { user_id, score, previous_score }) = message {
+            return (user_id, score, previous_score);
+        }
+    }
+}
+
+#[tokio::test]
+async fn score_changes_reach_subscribers_of_every_instance() {
+    let app = TestApp::spawn().await;
+    let shutdown = CancellationToken::new();
+    let mut first = replica(&app, &shutdown).await;
+    let mut second = replica(&app, &shutdown).await;
+
+    let (token, user_id) = app.login(5801).await;
+    let (status, _) = app.post("/game/update_score", Some(&token), json!({ "score": 40 })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.post("/game/update_score", Some(&token), json!({ "score": 150 })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Диапазон прежнего и нового счёта нужен подписчикам, чтобы понять, кого обогнали
+    for subscriber in [&mut first, &mut second] {
+        assert_eq!(next_score_change(subscriber).await, (user_id, 40, 0));
+        assert_eq!(next_score_change(subscriber).await, (user_id, 150, 40));
+    }
+    shutdown.cancel();
+}